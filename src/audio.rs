@@ -2,11 +2,13 @@ use super::*;
 use kira::{
     manager::{backend::DefaultBackend, AudioManager, AudioManagerSettings},
     sound::static_sound::{StaticSoundData, StaticSoundSettings},
+    tween::Tween,
+    Volume,
 };
 use std::io::Cursor;
 
 #[derive(SingleResource)]
-pub struct Audio(AudioManager, Vec<StaticSoundData>);
+pub struct Audio(AudioManager, Vec<StaticSoundData>, f64);
 
 impl Audio {
     pub fn new(sounds: &[&[u8]]) -> Self {
@@ -21,10 +23,22 @@ impl Audio {
             })
             .collect::<Vec<_>>();
 
-        Self(manager, sounds)
+        Self(manager, sounds, 1.0)
     }
 
     pub fn play(&mut self, idx: usize) {
-        self.0.play(self.1[idx].clone()).unwrap();
+        let volume = Volume::Amplitude(self.2);
+        self.0
+            .play(self.1[idx].with_modified_settings(|settings| settings.volume(volume)))
+            .unwrap();
+    }
+
+    /// Volumes are linear factors where 1.0 leaves sounds unchanged.
+    pub fn set_volume(&mut self, master: f64, effects: f64) {
+        self.0
+            .main_track()
+            .set_volume(Volume::Amplitude(master), Tween::default())
+            .unwrap();
+        self.2 = effects;
     }
 }
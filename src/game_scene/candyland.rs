@@ -1,28 +1,34 @@
 use super::*;
 
-const ABSOLUTE_STEAL_MAX: usize = 15;
-const ANGER_DURATION: Duration = Duration::from_secs(3);
-const ANGER_CHANCE: f64 = 0.2;
-
-#[derive(SingleResource, Default, Clone, Copy)]
-struct Candyland {
+#[derive(SingleResource, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct Candyland {
     candy_stolen: usize,
-    time_of_anger: Option<Instant>,
+    time_of_anger: Option<GameInstant>,
+    #[serde(default)]
+    house: HouseKind,
 }
 
 impl Candyland {
     pub fn try_anger(&mut self, galaxy: &Galaxy) {
-        let mut trigger_anger = self.candy_stolen >= ABSOLUTE_STEAL_MAX;
+        let rules = rules(galaxy);
+        let mut trigger_anger = self.candy_stolen >= rules.steal_max;
 
-        let mut rng = rand::thread_rng();
+        let mut rng = game_rng(galaxy, RngStream::Candyland);
 
-        if rng.gen_bool(ANGER_CHANCE) {
+        if rng.gen_bool((rules.anger_chance * self.house.anger_scale()).min(1.0)) {
             trigger_anger = true;
         }
 
-        if trigger_anger && self.candy_stolen > 4 {
+        if trigger_anger && self.candy_stolen >= tuning(galaxy).house.min_stolen_for_anger {
             audio_door(galaxy);
-            self.time_of_anger = Some(Instant::now());
+            spawn_particle_emitter(
+                galaxy,
+                GameState::CandyLand,
+                glm::vec2(0.0, -0.35),
+                ParticleEmitterConfig::door_dust(),
+            );
+            self.time_of_anger = Some(game_now(galaxy));
+            notoriety_raise(galaxy, tuning(galaxy).notoriety.granny);
         }
     }
 
@@ -35,94 +41,142 @@ pub fn candyland_init(galaxy: &Galaxy) {
     galaxy.insert_resource(Candyland::single_resource(), Candyland::default());
 }
 
-pub fn candyland_update(galaxy: &Galaxy) {
-    let mut camera = galaxy
+pub fn candyland_snapshot(galaxy: &Galaxy) -> Candyland {
+    *galaxy
+        .get_resource::<Candyland, _>(Candyland::single_resource())
+        .unwrap()
+}
+
+pub fn candyland_restore(galaxy: &Galaxy, candyland: Candyland) {
+    **galaxy
+        .get_mut_resource::<Candyland, _>(Candyland::single_resource())
+        .unwrap() = candyland;
+}
+
+/// Remember which kind of house the player is walking into.
+pub fn candyland_enter_house(galaxy: &Galaxy, kind: HouseKind) {
+    galaxy
+        .get_mut_resource::<Candyland, _>(Candyland::single_resource())
+        .unwrap()
+        .house = kind;
+}
+
+pub fn candyland_enter(galaxy: &Galaxy) {
+    galaxy
         .get_mut_resource::<RendererCamera, _>(RendererCamera::single_resource())
+        .unwrap()
+        .position = glm::vec2(0.0, 0.0);
+}
+
+pub fn candyland_reset(galaxy: &Galaxy) {
+    galaxy
+        .get_mut_resource::<Candyland, _>(Candyland::single_resource())
+        .unwrap()
+        .reset();
+}
+
+pub fn candyland_update(galaxy: &Galaxy) {
+    let mut candyland = galaxy
+        .get_mut_resource::<Candyland, _>(Candyland::single_resource())
         .unwrap();
+    let mut player = galaxy
+        .get_mut_resource::<Player, _>(Player::single_resource())
+        .unwrap();
+
+    let keys = key_bindings(galaxy);
+    for input in keyboard_inputs(galaxy) {
+        if input.virtual_keycode == Some(keys.pause) && input.state == ElementState::Released {
+            drop(candyland);
+            drop(player);
+            pause_open(galaxy, PausePage::Main);
+            return;
+        }
+        let leave = input.virtual_keycode == Some(keys.leave)
+            || input.virtual_keycode == Some(VirtualKeyCode::Escape);
+        if leave && candyland.time_of_anger.is_none() {
+            transition_to(galaxy, TransitionKind::Iris, GameState::Map);
+        }
+        if input.virtual_keycode == Some(keys.interact)
+            && input.state == ElementState::Released
+            && candyland.time_of_anger.is_none()
+        {
+            candyland.candy_stolen += 1;
+            player.score += 1;
+            spawn_score_increment(galaxy);
+            audio_coin(galaxy);
+            spawn_particle_emitter(
+                galaxy,
+                GameState::CandyLand,
+                glm::vec2(-0.5, -0.4),
+                ParticleEmitterConfig::candy_sparkle(),
+            );
+            candyland.try_anger(galaxy);
+        }
+    }
 
-    camera.position = glm::vec2(0.0, 0.0);
+    if let Some(time_of_anger) = candyland.time_of_anger {
+        if game_now(galaxy).duration_since(time_of_anger) > tuning(galaxy).house.anger_duration
+            && transition_to(galaxy, TransitionKind::Fade, GameState::Map)
+        {
+            let lost = player.score.min(tuning(galaxy).house.granny_penalty);
+            player.score -= lost;
+            stats_record(galaxy, |stats| stats.candy_lost += lost);
+            audio_punch(galaxy);
+            spawn_score_decrement(galaxy);
+        }
+    }
+}
 
+pub fn candyland_render(galaxy: &Galaxy) {
     galaxy.insert_event(RendererDrawSprite(Sprite {
-        visible: 1.0,
         texture_index: SPRITE_HOUSE,
-        color: glm::vec3(1.0, 1.0, 1.0),
+        color: glm::vec4(1.0, 1.0, 1.0, 1.0),
         rotation: 0.0,
         scale: glm::vec2(1.0, 1.0),
         position: glm::vec2(0.0, 0.0),
+        unlit: false,
+        ..Default::default()
     }));
     galaxy.insert_event(RendererDrawSprite(Sprite {
-        visible: 1.0,
         texture_index: SPRITE_TAKEONE,
-        color: glm::vec3(1.0, 1.0, 1.0),
+        color: glm::vec4(1.0, 1.0, 1.0, 1.0),
         rotation: 0.0,
         scale: glm::vec2(0.4, 0.4),
         position: glm::vec2(-0.5, -0.7),
+        unlit: false,
+        ..Default::default()
     }));
     galaxy.insert_event(RendererDrawSprite(Sprite {
-        visible: 1.0,
         texture_index: SPRITE_BASKET,
-        color: glm::vec3(1.0, 1.0, 1.0),
+        color: glm::vec4(1.0, 1.0, 1.0, 1.0),
         rotation: 0.0,
         scale: glm::vec2(0.4, 0.4),
         position: glm::vec2(-0.5, -0.4),
+        unlit: false,
+        ..Default::default()
     }));
     galaxy.insert_event(RendererDrawSprite(Sprite {
-        visible: 1.0,
         texture_index: SPRITE_INPINFO,
-        color: glm::vec3(1.0, 1.0, 1.0),
+        color: glm::vec4(1.0, 1.0, 1.0, 1.0),
         rotation: 0.0,
         scale: glm::vec2(0.4, 0.4),
         position: glm::vec2(0.75, -0.4),
+        unlit: false,
+        ..Default::default()
     }));
 
-    let mut candyland = galaxy
-        .get_mut_resource::<Candyland, _>(Candyland::single_resource())
+    let candyland = galaxy
+        .get_resource::<Candyland, _>(Candyland::single_resource())
         .unwrap();
-    let mut player = galaxy
-        .get_mut_resource::<Player, _>(Player::single_resource())
-        .unwrap();
-
-    for ev in galaxy.get_events::<WindowEvent>() {
-        if let WindowEventData::KeyboardInput { input, .. } = ev.0 {
-            if input.virtual_keycode == Some(VirtualKeyCode::Escape)
-                && candyland.time_of_anger.is_none()
-            {
-                **galaxy
-                    .get_mut_resource::<GameState, _>(GameState::single_resource())
-                    .unwrap() = GameState::Map;
-                candyland.reset();
-            }
-            if input.virtual_keycode == Some(VirtualKeyCode::E)
-                && input.state == ElementState::Released
-                && candyland.time_of_anger.is_none()
-            {
-                candyland.candy_stolen += 1;
-                player.score += 1;
-                spawn_score_increment(galaxy);
-                audio_coin(galaxy);
-                candyland.try_anger(galaxy);
-            }
-        }
-    }
-
-    if let Some(time_of_anger) = candyland.time_of_anger {
+    if candyland.time_of_anger.is_some() {
         galaxy.insert_event(RendererDrawSprite(Sprite {
-            visible: 1.0,
             texture_index: SPRITE_GRANNY,
-            color: glm::vec3(1.0, 1.0, 1.0),
+            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
             rotation: 0.0,
             scale: glm::vec2(0.8, 0.8),
             position: glm::vec2(0.0, 0.0),
+            unlit: false,
+            ..Default::default()
         }));
-
-        if Instant::now().duration_since(time_of_anger) > ANGER_DURATION {
-            **galaxy
-                .get_mut_resource::<GameState, _>(GameState::single_resource())
-                .unwrap() = GameState::Map;
-            player.score = (player.score as isize - 6).clamp(0, 9999) as usize;
-            audio_punch(galaxy);
-            spawn_score_decrement(galaxy);
-            candyland.reset();
-        }
     }
 }
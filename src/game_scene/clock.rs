@@ -0,0 +1,69 @@
+use super::*;
+
+const MAX_TICK: Duration = Duration::from_millis(100);
+
+/// A point in game time. Unlike `Instant`, game time stands still while the game is paused.
+#[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GameInstant(Duration);
+
+impl GameInstant {
+    pub fn duration_since(&self, earlier: GameInstant) -> Duration {
+        self.0.saturating_sub(earlier.0)
+    }
+}
+
+/// Both clocks advance by `FrameDelta`, so replays see exactly the same times.
+#[derive(SingleResource, Default, Clone, Copy)]
+pub struct GameClock {
+    now: GameInstant,
+    /// Keeps running while paused, for menus and transitions.
+    real: GameInstant,
+}
+
+pub fn game_clock_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(GameClock::single_resource(), GameClock::default());
+}
+
+/// Jump the clock to `now`, for picking up timers from a saved game.
+pub fn game_clock_set(galaxy: &Galaxy, now: GameInstant) {
+    galaxy
+        .get_mut_resource::<GameClock, _>(GameClock::single_resource())
+        .unwrap()
+        .now = now;
+}
+
+pub fn game_now(galaxy: &Galaxy) -> GameInstant {
+    galaxy
+        .get_resource::<GameClock, _>(GameClock::single_resource())
+        .unwrap()
+        .now
+}
+
+pub fn real_now(galaxy: &Galaxy) -> GameInstant {
+    galaxy
+        .get_resource::<GameClock, _>(GameClock::single_resource())
+        .unwrap()
+        .real
+}
+
+pub fn game_clock_update(galaxy: &Galaxy) {
+    // Settings can be opened from the pause menu, so check the whole stack.
+    let paused =
+        state_on_stack(galaxy, GameState::Pause) || state_on_stack(galaxy, GameState::Editor);
+
+    let delta = galaxy
+        .get_resource::<FrameDelta, _>(FrameDelta::single_resource())
+        .unwrap()
+        .0;
+    let mut clock = galaxy
+        .get_mut_resource::<GameClock, _>(GameClock::single_resource())
+        .unwrap();
+
+    // Clamped so a stalled window doesn't fast forward every timer at once.
+    let tick = delta.min(MAX_TICK);
+    clock.real.0 += tick;
+
+    if !paused {
+        clock.now.0 += tick;
+    }
+}
@@ -0,0 +1,202 @@
+use super::*;
+
+const HIGH_SCORES_FILE: &str = "highscores.ron";
+const MAX_ENTRIES: usize = 10;
+const BACKDROP_ALPHA: f32 = 0.85;
+const ROW_START_Y: f32 = 0.25;
+const ROW_SPACING: f32 = 0.09;
+const ROW_SIZE: f32 = 0.055;
+const HINT_Y: f32 = -0.8;
+const HEADER_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 0.55, 0.1, 1.0);
+const ROW_COLOR: glm::Vec4 = glm::Vec4::new(0.8, 0.8, 0.8, 1.0);
+const LATEST_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 1.0, 0.4, 1.0);
+
+#[derive(Clone, Serialize, Deserialize)]
+struct HighScoreEntry {
+    score: usize,
+    /// Game time it took to reach the target score, if it was reached at all.
+    time_to_win: Option<Duration>,
+    houses_raided: usize,
+    kids_punched: usize,
+    candy_lost: usize,
+    /// YYYY-MM-DD
+    date: String,
+}
+
+impl HighScoreEntry {
+    fn row(&self, rank: usize) -> String {
+        let time = match self.time_to_win {
+            Some(time) => format!("{:02}:{:02}", time.as_secs() / 60, time.as_secs() % 60),
+            None => String::from("--:--"),
+        };
+        table_row(&[
+            &(rank + 1).to_string(),
+            &self.score.to_string(),
+            &time,
+            &self.houses_raided.to_string(),
+            &self.kids_punched.to_string(),
+            &self.candy_lost.to_string(),
+            &self.date,
+        ])
+    }
+}
+
+fn table_row(columns: &[&str; 7]) -> String {
+    format!(
+        "{:>2} {:>5} {:>6} {:>6} {:>5} {:>5} {:>10}",
+        columns[0], columns[1], columns[2], columns[3], columns[4], columns[5], columns[6]
+    )
+}
+
+#[derive(SingleResource, Default, Clone, Serialize, Deserialize)]
+struct HighScores {
+    entries: Vec<HighScoreEntry>,
+    /// Where the last recorded run landed, to highlight it.
+    #[serde(skip)]
+    latest: Option<usize>,
+}
+
+pub fn high_scores_init(galaxy: &Galaxy) {
+    let high_scores = storage_read(HIGH_SCORES_FILE)
+        .and_then(|data| ron::from_str::<HighScores>(&data).ok())
+        .unwrap_or_default();
+    galaxy.insert_resource(HighScores::single_resource(), high_scores);
+}
+
+pub fn high_scores_exist(galaxy: &Galaxy) -> bool {
+    !galaxy
+        .get_resource::<HighScores, _>(HighScores::single_resource())
+        .unwrap()
+        .entries
+        .is_empty()
+}
+
+/// Add the current run to the table.
+pub fn high_scores_record(galaxy: &Galaxy) {
+    let score = galaxy
+        .get_resource::<Player, _>(Player::single_resource())
+        .unwrap()
+        .score;
+    let stats = stats(galaxy);
+
+    let entry = HighScoreEntry {
+        score,
+        time_to_win: stats
+            .reached_target
+            .map(|reached| reached.duration_since(stats.started)),
+        houses_raided: stats.houses_raided,
+        kids_punched: stats.kids_punched,
+        candy_lost: stats.candy_lost,
+        date: today(),
+    };
+
+    let mut high_scores = galaxy
+        .get_mut_resource::<HighScores, _>(HighScores::single_resource())
+        .unwrap();
+
+    // Higher scores first, then quicker wins.
+    let rank = high_scores
+        .entries
+        .iter()
+        .position(|other| {
+            entry.score > other.score
+                || (entry.score == other.score
+                    && entry.time_to_win.unwrap_or(Duration::MAX)
+                        < other.time_to_win.unwrap_or(Duration::MAX))
+        })
+        .unwrap_or(high_scores.entries.len());
+
+    high_scores.entries.insert(rank, entry);
+    high_scores.entries.truncate(MAX_ENTRIES);
+    high_scores.latest = (rank < MAX_ENTRIES).then_some(rank);
+
+    storage_write(
+        HIGH_SCORES_FILE,
+        &ron::ser::to_string_pretty(&*high_scores, ron::ser::PrettyConfig::default()).unwrap(),
+    );
+}
+
+pub fn high_scores_update(galaxy: &Galaxy) {
+    for input in keyboard_inputs(galaxy) {
+        if matches!(
+            menu_input(&input),
+            Some(MenuInput::Back | MenuInput::Select)
+        ) {
+            state_pop(galaxy);
+        }
+    }
+}
+
+pub fn high_scores_render(galaxy: &Galaxy) {
+    let camera = *galaxy
+        .get_resource::<RendererCamera, _>(RendererCamera::single_resource())
+        .unwrap();
+    let high_scores = galaxy
+        .get_resource::<HighScores, _>(HighScores::single_resource())
+        .unwrap();
+
+    draw_menu(galaxy, "HIGH SCORES", &[], 0, BACKDROP_ALPHA);
+
+    draw_text(
+        galaxy,
+        &table_row(&["#", "SCORE", "TIME", "HOUSES", "KIDS", "LOST", "DATE"]),
+        camera.position + glm::vec2(0.0, ROW_START_Y),
+        ROW_SIZE,
+        HEADER_COLOR,
+    );
+
+    for (i, entry) in high_scores.entries.iter().enumerate() {
+        draw_text(
+            galaxy,
+            &entry.row(i),
+            camera.position + glm::vec2(0.0, ROW_START_Y - (i + 1) as f32 * ROW_SPACING),
+            ROW_SIZE,
+            if high_scores.latest == Some(i) {
+                LATEST_COLOR
+            } else {
+                ROW_COLOR
+            },
+        );
+    }
+
+    draw_text(
+        galaxy,
+        "Press Enter to go back",
+        camera.position + glm::vec2(0.0, HINT_Y),
+        ROW_SIZE,
+        ROW_COLOR,
+    );
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn today() -> String {
+    let days = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+        / 86400;
+
+    // Howard Hinnant's days to civil date conversion.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn today() -> String {
+    let date = js_sys::Date::new_0();
+    format!(
+        "{:04}-{:02}-{:02}",
+        date.get_full_year(),
+        date.get_month() + 1,
+        date.get_date()
+    )
+}
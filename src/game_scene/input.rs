@@ -0,0 +1,17 @@
+use super::*;
+
+/// Keyboard input meant for gameplay. Empty while a transition is running.
+pub fn keyboard_inputs(galaxy: &Galaxy) -> Vec<KeyboardInput> {
+    if transition_active(galaxy) {
+        return vec![];
+    }
+
+    galaxy
+        .get_events::<WindowEvent>()
+        .iter()
+        .filter_map(|ev| match ev.0 {
+            WindowEventData::KeyboardInput { input, .. } => Some(input),
+            _ => None,
+        })
+        .collect()
+}
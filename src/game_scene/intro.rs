@@ -1,19 +1,115 @@
 use super::*;
 
+const BACKDROP_ALPHA: f32 = 0.4;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TitleItem {
+    NewGame,
+    Continue,
+    Settings,
+    HighScores,
+    Quit,
+}
+
+const TITLE_ITEMS: [TitleItem; 5] = [
+    TitleItem::NewGame,
+    TitleItem::Continue,
+    TitleItem::Settings,
+    TitleItem::HighScores,
+    TitleItem::Quit,
+];
+
+#[derive(SingleResource, Default, Clone, Copy)]
+struct TitleMenu {
+    selected: usize,
+    can_continue: bool,
+    has_high_scores: bool,
+}
+
+impl TitleMenu {
+    fn items(&self) -> Vec<MenuItem> {
+        TITLE_ITEMS
+            .iter()
+            .map(|item| match item {
+                TitleItem::NewGame => MenuItem::new("New Game"),
+                TitleItem::Continue => MenuItem::new("Continue").enabled(self.can_continue),
+                TitleItem::Settings => MenuItem::new("Settings"),
+                TitleItem::HighScores => MenuItem::new("High Scores").enabled(self.has_high_scores),
+                TitleItem::Quit => MenuItem::new("Quit"),
+            })
+            .collect()
+    }
+}
+
+pub fn intro_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(TitleMenu::single_resource(), TitleMenu::default());
+}
+
+pub fn intro_enter(galaxy: &Galaxy) {
+    galaxy
+        .get_mut_resource::<RendererCamera, _>(RendererCamera::single_resource())
+        .unwrap()
+        .position = glm::vec2(0.0, 0.0);
+
+    let can_continue = save_exists();
+    let has_high_scores = high_scores_exist(galaxy);
+    let mut menu = galaxy
+        .get_mut_resource::<TitleMenu, _>(TitleMenu::single_resource())
+        .unwrap();
+    menu.can_continue = can_continue;
+    menu.has_high_scores = has_high_scores;
+    menu.selected = if can_continue {
+        TITLE_ITEMS
+            .iter()
+            .position(|&item| item == TitleItem::Continue)
+            .unwrap()
+    } else {
+        0
+    };
+}
+
 pub fn intro_update(galaxy: &Galaxy) {
+    let mut menu = galaxy
+        .get_mut_resource::<TitleMenu, _>(TitleMenu::single_resource())
+        .unwrap();
+    let items = menu.items();
+
+    for input in keyboard_inputs(galaxy) {
+        let Some(input) = menu_input(&input) else {
+            continue;
+        };
+        if input != MenuInput::Select {
+            menu_navigate(&mut menu.selected, &items, input);
+            continue;
+        }
+
+        match TITLE_ITEMS[menu.selected] {
+            TitleItem::NewGame => {
+                new_game(galaxy);
+            }
+            TitleItem::Continue => {
+                continue_game(galaxy);
+            }
+            TitleItem::HighScores => state_push(galaxy, GameState::HighScores),
+            TitleItem::Settings => state_push(galaxy, GameState::Settings),
+            TitleItem::Quit => galaxy.set_exit(),
+        }
+    }
+}
+
+pub fn intro_render(galaxy: &Galaxy) {
     galaxy.insert_event(RendererDrawSprite(Sprite {
-        visible: 1.0,
         texture_index: SPRITE_INTRO,
-        color: glm::vec3(1.0, 1.0, 1.0),
+        color: glm::vec4(1.0, 1.0, 1.0, 1.0),
         rotation: 0.0,
         scale: glm::vec2(1.9, 1.9),
         position: glm::vec2(0.0, 0.0),
+        unlit: true,
+        ..Default::default()
     }));
-    for ev in galaxy.get_events::<WindowEvent>() {
-        if let WindowEventData::KeyboardInput { .. } = ev.0 {
-            **galaxy
-                .get_mut_resource::<GameState, _>(GameState::single_resource())
-                .unwrap() = GameState::Map;
-        }
-    }
+
+    let menu = *galaxy
+        .get_resource::<TitleMenu, _>(TitleMenu::single_resource())
+        .unwrap();
+    draw_menu(galaxy, "", &menu.items(), menu.selected, BACKDROP_ALPHA);
 }
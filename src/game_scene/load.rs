@@ -1,5 +1,6 @@
 use super::*;
 
+pub const SPRITE_WHITE: usize = 0;
 pub const SPRITE_HOUSE: usize = 1;
 pub const SPRITE_CANDY: usize = 2;
 pub const SPRITE_BASKET: usize = 3;
@@ -15,6 +16,13 @@ pub const SPRITE_INTRO: usize = 23;
 pub const SPRITE_OUTRO: usize = 24;
 pub const SPRITE_KIDPUNCH: usize = 25;
 pub const SPRITE_SMINUS: usize = 26;
+pub const SPRITE_PARTICLE_SOFT: usize = 27;
+pub const SPRITE_PARTICLE_STAR: usize = 28;
+pub const SPRITE_PARTICLE_LEAF: usize = 29;
+pub const SPRITE_JACK_O_LANTERN: usize = 30;
+pub const SPRITE_IRIS: usize = 31;
+pub const SPRITE_FONT: usize = 32;
+pub const SPRITE_TILES: usize = 33;
 
 pub fn load_init(galaxy: &Galaxy) {
     let white: [u8; 4] = [255, 255, 255, 255];
@@ -50,6 +58,13 @@ pub fn load_init(galaxy: &Galaxy) {
             TextureData::load(include_bytes!("../../assets/outro.png")),
             TextureData::load(include_bytes!("../../assets/kidpunch.jpeg")),
             TextureData::load(include_bytes!("../../assets/symbols/minus.png")),
+            particle_soft_texture(),
+            particle_star_texture(),
+            particle_leaf_texture(),
+            map::jack_o_lantern_texture(),
+            iris_texture(),
+            TextureData::load(include_bytes!("../../assets/symbols/bitmap.png")),
+            map::tile_atlas_texture(),
         ]),
     );
 
@@ -99,3 +114,10 @@ pub fn audio_no(galaxy: &Galaxy) {
         .unwrap()
         .play(4);
 }
+
+pub fn audio_set_volume(galaxy: &Galaxy, master: f64, effects: f64) {
+    galaxy
+        .get_mut_resource::<Audio, _>(Audio::single_resource())
+        .unwrap()
+        .set_volume(master, effects);
+}
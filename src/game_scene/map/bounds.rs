@@ -2,6 +2,35 @@ use super::*;
 
 pub const MAP_BOUND_SIZE: f32 = 10.0;
 
+/// The walkable area of a level.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct LevelBounds {
+    pub min: glm::Vec2,
+    pub max: glm::Vec2,
+}
+
+impl LevelBounds {
+    pub fn contains(&self, position: glm::Vec2) -> bool {
+        position.x >= self.min.x
+            && position.x <= self.max.x
+            && position.y >= self.min.y
+            && position.y <= self.max.y
+    }
+
+    pub fn clamp(&self, position: glm::Vec2) -> glm::Vec2 {
+        glm::vec2(
+            position.x.clamp(self.min.x, self.max.x),
+            position.y.clamp(self.min.y, self.max.y),
+        )
+    }
+}
+
 pub fn bounds_check_update(galaxy: &Galaxy) {
-    todo!()
+    let level = galaxy
+        .get_resource::<Level, _>(Level::single_resource())
+        .unwrap();
+    galaxy
+        .get_mut_resource::<Player, _>(Player::single_resource())
+        .unwrap()
+        .keep_within(&level.bounds);
 }
@@ -1,14 +1,8 @@
 use super::*;
 
-const FRICTION_FACTOR: f32 = 0.001;
-const PICKUP_RANGE: f32 = 0.2;
-const MOVEMENT_RANGE: f32 = 2.0;
-const MOVEMENT_SCALAR: f32 = 0.0005;
-const MAX_TRAVEL: f32 = 0.01;
-
-#[derive(CheapComponent, Clone, Copy)]
+#[derive(CheapComponent, Clone, Copy, Serialize, Deserialize)]
 pub struct Candy {
-    position: glm::Vec2,
+    pub position: glm::Vec2,
     velocity: glm::Vec2,
     travel: f32,
 }
@@ -16,18 +10,19 @@ pub struct Candy {
 impl Candy {
     pub fn as_sprite(&self) -> Sprite {
         Sprite {
-            visible: 1.0,
             texture_index: SPRITE_CANDY,
-            color: glm::vec3(1.0, 1.0, 1.0),
+            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
             rotation: 0.0,
             scale: glm::vec2(0.1, 0.1),
             position: self.position,
+            unlit: false,
+            ..Default::default()
         }
     }
 }
 
 pub fn blow_candy(galaxy: &Galaxy, position: glm::Vec2) {
-    let mut rng = rand::thread_rng();
+    let mut rng = game_rng(galaxy, RngStream::Candy);
     let candy_count: i32 = rng.gen_range(0..4);
 
     for _ in 0..candy_count {
@@ -43,21 +38,22 @@ pub fn blow_candy(galaxy: &Galaxy, position: glm::Vec2) {
 }
 
 pub fn candy_movement_update(galaxy: &Galaxy) {
+    let tuning = tuning(galaxy).candy;
     for candy in galaxy.query::<&mut Candy>().iter() {
-        if candy.travel < MAX_TRAVEL {
+        if candy.travel < tuning.max_travel {
             let friction_factor = if candy.velocity.x.abs() < 0.01 {
                 8.0
             } else {
                 1.0
             };
-            candy.velocity.x -= candy.velocity.x * FRICTION_FACTOR * friction_factor;
+            candy.velocity.x -= candy.velocity.x * tuning.friction_factor * friction_factor;
 
             let friction_factor = if candy.velocity.y.abs() < 0.01 {
                 8.0
             } else {
                 1.0
             };
-            candy.velocity.y -= candy.velocity.y * FRICTION_FACTOR * friction_factor;
+            candy.velocity.y -= candy.velocity.y * tuning.friction_factor * friction_factor;
 
             candy.position.x += candy.velocity.x;
             candy.position.y += candy.velocity.y;
@@ -71,10 +67,20 @@ pub fn candy_in_range_movement_update(galaxy: &Galaxy) {
     let player = galaxy
         .get_resource::<Player, _>(Player::single_resource())
         .unwrap();
+    let tuning = tuning(galaxy).candy;
+    let index = galaxy
+        .get_resource::<SpatialIndex, _>(SpatialIndex::single_resource())
+        .unwrap();
 
-    for candy in galaxy.query::<&mut Candy>().iter() {
-        if glm::distance(&player.position, &candy.position) < MOVEMENT_RANGE {
-            candy.position += glm::normalize(&(candy.position - player.position)) * MOVEMENT_SCALAR;
+    let mut candies = galaxy.query::<&mut Candy>().iter().collect::<Vec<_>>();
+    for i in index
+        .candy
+        .query_radius(player.position, tuning.movement_range)
+    {
+        let candy = &mut *candies[i];
+        let away = candy.position - player.position;
+        if glm::length(&away) > f32::EPSILON {
+            candy.position += glm::normalize(&away) * tuning.movement_scalar;
         }
     }
 }
@@ -83,14 +89,24 @@ pub fn candy_in_range_pickup_update(galaxy: &Galaxy) {
     let mut player = galaxy
         .get_mut_resource::<Player, _>(Player::single_resource())
         .unwrap();
+    let pickup_range = tuning(galaxy).candy.pickup_range;
+    let index = galaxy
+        .get_resource::<SpatialIndex, _>(SpatialIndex::single_resource())
+        .unwrap();
 
-    for (e, candy) in galaxy.query::<&mut Candy>().eiter() {
-        if glm::distance(&player.position, &candy.position) < PICKUP_RANGE {
-            spawn_score_increment(galaxy);
-            player.score += 1;
-            audio_coin(galaxy);
-            galaxy.remove_entity(e);
-        }
+    let candies = galaxy.query::<&Candy>().eiter().collect::<Vec<_>>();
+    for i in index.candy.query_radius(player.position, pickup_range) {
+        let (e, candy) = candies[i];
+        spawn_score_increment(galaxy);
+        player.score += 1;
+        audio_coin(galaxy);
+        spawn_particle_emitter(
+            galaxy,
+            GameState::Map,
+            candy.position,
+            ParticleEmitterConfig::candy_sparkle(),
+        );
+        galaxy.remove_entity(e);
     }
 }
 
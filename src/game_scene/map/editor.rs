@@ -0,0 +1,597 @@
+//! Debug builds only. F1 on the map opens the current level in the editor.
+//! Arrow keys or WASD fly the camera and 1 to 7 pick what gets placed.
+//! Left click places or drags, right click or Delete removes, K changes a house's kind or
+//! swaps an adult between parent and police,
+//! G toggles grid snap and F5 saves to `assets/levels`. F1 or Escape goes back to playing it.
+
+use super::*;
+
+pub const EDITOR_KEY: VirtualKeyCode = VirtualKeyCode::F1;
+const SAVE_KEY: VirtualKeyCode = VirtualKeyCode::F5;
+const CAMERA_STEP: f32 = 0.15;
+const SNAP_STEP: f32 = 0.1;
+const GRID_LINE_STEP: f32 = 0.5;
+const PICK_RADIUS: f32 = 0.25;
+const LINE_WIDTH: f32 = 0.01;
+const GRID_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 1.0, 1.0, 0.08);
+const ADULT_PATROL_COLOR: glm::Vec4 = glm::Vec4::new(0.55, 0.65, 1.0, 0.5);
+const BOUNDS_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 0.3, 0.3, 0.8);
+const SELECTED_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 0.9, 0.3, 1.0);
+const GHOST_ALPHA: f32 = 0.4;
+const HUD_SIZE: f32 = 0.045;
+const HUD_COLOR: glm::Vec4 = glm::Vec4::new(0.9, 0.9, 0.9, 1.0);
+const TITLE_POSITION: glm::Vec2 = glm::Vec2::new(0.0, 0.92);
+const STATUS_POSITION: glm::Vec2 = glm::Vec2::new(0.0, -0.92);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EditorTool {
+    House,
+    Kid,
+    JackOLantern,
+    StreetLight,
+    PlayerSpawn,
+    Parent,
+    Police,
+}
+
+impl EditorTool {
+    const ALL: [EditorTool; 7] = [
+        EditorTool::House,
+        EditorTool::Kid,
+        EditorTool::JackOLantern,
+        EditorTool::StreetLight,
+        EditorTool::PlayerSpawn,
+        EditorTool::Parent,
+        EditorTool::Police,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            EditorTool::House => "HOUSE",
+            EditorTool::Kid => "KID",
+            EditorTool::JackOLantern => "LANTERN",
+            EditorTool::StreetLight => "STREET LIGHT",
+            EditorTool::PlayerSpawn => "PLAYER",
+            EditorTool::Parent => "PARENT",
+            EditorTool::Police => "POLICE",
+        }
+    }
+}
+
+/// Something placed in the level being edited.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EditorItem {
+    House(usize),
+    Kid(usize),
+    Adult(usize),
+    Prop(usize),
+    PlayerSpawn,
+}
+
+#[derive(SingleResource)]
+pub struct Editor {
+    level: Level,
+    /// Saved as `assets/levels/{file}.ron`.
+    file: String,
+    camera: glm::Vec2,
+    /// In window pixels, as last reported by winit.
+    cursor: glm::Vec2,
+    tool: EditorTool,
+    selected: Option<EditorItem>,
+    dragging: bool,
+    snap: bool,
+    status: String,
+}
+
+impl Editor {
+    fn cursor_world(&self, galaxy: &Galaxy) -> glm::Vec2 {
+        let size = *galaxy
+            .get_resource::<WindowSize, _>(WindowSize::single_resource())
+            .unwrap();
+        let ndc = glm::vec2(
+            self.cursor.x / size.width.max(1) as f32 * 2.0 - 1.0,
+            1.0 - self.cursor.y / size.height.max(1) as f32 * 2.0,
+        );
+        let position = self.camera + ndc;
+        if self.snap {
+            glm::vec2(
+                (position.x / SNAP_STEP).round() * SNAP_STEP,
+                (position.y / SNAP_STEP).round() * SNAP_STEP,
+            )
+        } else {
+            position
+        }
+    }
+
+    fn items(&self) -> Vec<EditorItem> {
+        let mut items = vec![EditorItem::PlayerSpawn];
+        items.extend((0..self.level.houses.len()).map(EditorItem::House));
+        items.extend((0..self.level.kids.len()).map(EditorItem::Kid));
+        items.extend((0..self.level.adults.len()).map(EditorItem::Adult));
+        items.extend((0..self.level.props.len()).map(EditorItem::Prop));
+        items
+    }
+
+    fn position(&self, item: EditorItem) -> glm::Vec2 {
+        match item {
+            EditorItem::House(i) => self.level.houses[i].position,
+            EditorItem::Kid(i) => self.level.kids[i].position,
+            EditorItem::Adult(i) => self.level.adults[i].position,
+            EditorItem::Prop(i) => self.level.props[i].position,
+            EditorItem::PlayerSpawn => self.level.player_spawn,
+        }
+    }
+
+    fn position_mut(&mut self, item: EditorItem) -> &mut glm::Vec2 {
+        match item {
+            EditorItem::House(i) => &mut self.level.houses[i].position,
+            EditorItem::Kid(i) => &mut self.level.kids[i].position,
+            EditorItem::Adult(i) => &mut self.level.adults[i].position,
+            EditorItem::Prop(i) => &mut self.level.props[i].position,
+            EditorItem::PlayerSpawn => &mut self.level.player_spawn,
+        }
+    }
+
+    fn pick(&self, position: glm::Vec2) -> Option<EditorItem> {
+        let mut nearest = None;
+        let mut nearest_distance = PICK_RADIUS;
+        for item in self.items() {
+            let distance = glm::distance(&self.position(item), &position);
+            if distance < nearest_distance {
+                nearest = Some(item);
+                nearest_distance = distance;
+            }
+        }
+        nearest
+    }
+
+    fn place(&mut self, position: glm::Vec2) -> EditorItem {
+        let prop = |kind| LevelProp { kind, position };
+        let adult = |kind| LevelAdult {
+            kind,
+            position,
+            patrol: vec![],
+        };
+        match self.tool {
+            EditorTool::House => {
+                self.level.houses.push(LevelHouse {
+                    position,
+                    kind: HouseKind::Regular,
+                });
+                EditorItem::House(self.level.houses.len() - 1)
+            }
+            EditorTool::Kid => {
+                self.level.kids.push(LevelKid {
+                    position,
+                    patrol: vec![],
+                });
+                EditorItem::Kid(self.level.kids.len() - 1)
+            }
+            EditorTool::JackOLantern => {
+                self.level.props.push(prop(PropKind::JackOLantern));
+                EditorItem::Prop(self.level.props.len() - 1)
+            }
+            EditorTool::StreetLight => {
+                self.level.props.push(prop(PropKind::StreetLight));
+                EditorItem::Prop(self.level.props.len() - 1)
+            }
+            EditorTool::PlayerSpawn => {
+                self.level.player_spawn = position;
+                EditorItem::PlayerSpawn
+            }
+            EditorTool::Parent => {
+                self.level.adults.push(adult(AdultKind::Parent));
+                EditorItem::Adult(self.level.adults.len() - 1)
+            }
+            EditorTool::Police => {
+                self.level.adults.push(adult(AdultKind::Police));
+                EditorItem::Adult(self.level.adults.len() - 1)
+            }
+        }
+    }
+
+    fn remove(&mut self, item: EditorItem) {
+        match item {
+            EditorItem::House(i) => {
+                self.level.houses.remove(i);
+            }
+            EditorItem::Kid(i) => {
+                self.level.kids.remove(i);
+            }
+            EditorItem::Adult(i) => {
+                self.level.adults.remove(i);
+            }
+            EditorItem::Prop(i) => {
+                self.level.props.remove(i);
+            }
+            // There always has to be one.
+            EditorItem::PlayerSpawn => return,
+        }
+        self.selected = None;
+        self.dragging = false;
+    }
+
+    fn cycle_kind(&mut self) {
+        match self.selected {
+            Some(EditorItem::House(i)) => {
+                let house = &mut self.level.houses[i];
+                house.kind = match house.kind {
+                    HouseKind::Regular => HouseKind::Generous,
+                    HouseKind::Generous => HouseKind::Stingy,
+                    HouseKind::Stingy => HouseKind::Regular,
+                };
+            }
+            Some(EditorItem::Adult(i)) => {
+                let adult = &mut self.level.adults[i];
+                adult.kind = match adult.kind {
+                    AdultKind::Parent => AdultKind::Police,
+                    AdultKind::Police => AdultKind::Parent,
+                };
+            }
+            _ => (),
+        }
+    }
+
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn save(&mut self) -> Result<(), String> {
+        self.level.validate()?;
+        let path = format!(
+            "{}/assets/levels/{}.ron",
+            env!("CARGO_MANIFEST_DIR"),
+            self.file
+        );
+        let data = ron::ser::to_string_pretty(&self.level, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        std::fs::write(&path, data).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    fn save(&mut self) -> Result<(), String> {
+        Err("Saving levels needs the desktop build".to_string())
+    }
+}
+
+pub fn editor_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(
+        Editor::single_resource(),
+        Editor {
+            level: level(galaxy),
+            file: String::new(),
+            camera: glm::vec2(0.0, 0.0),
+            cursor: glm::vec2(0.0, 0.0),
+            tool: EditorTool::House,
+            selected: None,
+            dragging: false,
+            snap: true,
+            status: String::new(),
+        },
+    );
+}
+
+/// Start editing whatever level is being played.
+pub fn editor_enter(galaxy: &Galaxy) {
+    let source = galaxy
+        .get_resource::<Neighborhood, _>(Neighborhood::single_resource())
+        .unwrap()
+        .source
+        .clone();
+    let player = *galaxy
+        .get_resource::<Player, _>(Player::single_resource())
+        .unwrap();
+
+    let mut editor = galaxy
+        .get_mut_resource::<Editor, _>(Editor::single_resource())
+        .unwrap();
+    editor.level = level(galaxy);
+    editor.file = match source {
+        LevelSource::Generated(seed) => format!("seed_{}", seed),
+        LevelSource::File(name) => name,
+    };
+    editor.camera = player.position;
+    editor.selected = None;
+    editor.dragging = false;
+    editor.status = String::new();
+}
+
+/// Play the edited level from the start, keeping the score.
+fn editor_leave(galaxy: &Galaxy) {
+    let editor = galaxy
+        .get_resource::<Editor, _>(Editor::single_resource())
+        .unwrap();
+    let score = galaxy
+        .get_resource::<Player, _>(Player::single_resource())
+        .unwrap()
+        .score;
+
+    **galaxy
+        .get_mut_resource::<Level, _>(Level::single_resource())
+        .unwrap() = editor.level.clone();
+    drop(editor);
+
+    map_clear(galaxy);
+    map_populate(galaxy);
+    galaxy
+        .get_mut_resource::<Player, _>(Player::single_resource())
+        .unwrap()
+        .score = score;
+    state_set(galaxy, GameState::Map);
+}
+
+pub fn editor_update(galaxy: &Galaxy) {
+    let mut editor = galaxy
+        .get_mut_resource::<Editor, _>(Editor::single_resource())
+        .unwrap();
+
+    for input in keyboard_inputs(galaxy) {
+        let Some(key) = input.virtual_keycode else {
+            continue;
+        };
+        let pressed = input.state == ElementState::Pressed;
+
+        match key {
+            VirtualKeyCode::Up | VirtualKeyCode::W if pressed => editor.camera.y += CAMERA_STEP,
+            VirtualKeyCode::Down | VirtualKeyCode::S if pressed => editor.camera.y -= CAMERA_STEP,
+            VirtualKeyCode::Left | VirtualKeyCode::A if pressed => editor.camera.x -= CAMERA_STEP,
+            VirtualKeyCode::Right | VirtualKeyCode::D if pressed => editor.camera.x += CAMERA_STEP,
+            VirtualKeyCode::Key1
+            | VirtualKeyCode::Key2
+            | VirtualKeyCode::Key3
+            | VirtualKeyCode::Key4
+            | VirtualKeyCode::Key5
+            | VirtualKeyCode::Key6
+            | VirtualKeyCode::Key7
+                if pressed =>
+            {
+                editor.tool = EditorTool::ALL[key as usize - VirtualKeyCode::Key1 as usize];
+            }
+            VirtualKeyCode::G if pressed => editor.snap = !editor.snap,
+            VirtualKeyCode::K if pressed => editor.cycle_kind(),
+            VirtualKeyCode::Delete | VirtualKeyCode::Back if pressed => {
+                if let Some(item) = editor.selected {
+                    editor.remove(item);
+                }
+            }
+            SAVE_KEY if pressed => {
+                editor.status = match editor.save() {
+                    Ok(()) => {
+                        // Save games pick the level up from the file from now on.
+                        galaxy
+                            .get_mut_resource::<Neighborhood, _>(Neighborhood::single_resource())
+                            .unwrap()
+                            .source = LevelSource::File(editor.file.clone());
+                        format!("Saved {}.ron", editor.file)
+                    }
+                    Err(e) => e,
+                };
+            }
+            EDITOR_KEY | VirtualKeyCode::Escape if !pressed => match editor.level.validate() {
+                Ok(()) => {
+                    drop(editor);
+                    editor_leave(galaxy);
+                    return;
+                }
+                Err(e) => editor.status = e,
+            },
+            _ => (),
+        }
+    }
+
+    for ev in galaxy.get_events::<WindowEvent>() {
+        match ev.0 {
+            WindowEventData::CursorMoved { position, .. } => {
+                editor.cursor = glm::vec2(position.x as f32, position.y as f32);
+                if let (true, Some(item)) = (editor.dragging, editor.selected) {
+                    let position = editor.cursor_world(galaxy);
+                    *editor.position_mut(item) = position;
+                }
+            }
+            WindowEventData::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                if state == ElementState::Released {
+                    editor.dragging = false;
+                    continue;
+                }
+                let position = editor.cursor_world(galaxy);
+                let item = match editor.pick(position) {
+                    Some(item) => item,
+                    None => editor.place(position),
+                };
+                editor.selected = Some(item);
+                editor.dragging = true;
+            }
+            WindowEventData::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Right,
+                ..
+            } => {
+                let position = editor.cursor_world(galaxy);
+                if let Some(item) = editor.pick(position) {
+                    editor.remove(item);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    galaxy
+        .get_mut_resource::<RendererCamera, _>(RendererCamera::single_resource())
+        .unwrap()
+        .position = editor.camera;
+}
+
+fn draw_line(galaxy: &Galaxy, from: glm::Vec2, to: glm::Vec2, color: glm::Vec4) {
+    let offset = to - from;
+    galaxy.insert_event(RendererDrawSprite(Sprite {
+        texture_index: SPRITE_WHITE,
+        position: (from + to) * 0.5,
+        rotation: offset.y.atan2(offset.x),
+        scale: glm::vec2(glm::length(&offset).max(LINE_WIDTH), LINE_WIDTH),
+        color,
+        unlit: true,
+        ..Default::default()
+    }));
+}
+
+fn tool_sprite(tool: EditorTool, position: glm::Vec2, kind: HouseKind) -> Sprite {
+    let white = glm::vec4(1.0, 1.0, 1.0, 1.0);
+    let (texture_index, scale, color) = match tool {
+        EditorTool::House => (
+            SPRITE_HOUSE,
+            0.7,
+            match kind {
+                HouseKind::Regular => white,
+                HouseKind::Generous => glm::vec4(1.0, 0.8, 0.5, 1.0),
+                HouseKind::Stingy => glm::vec4(0.6, 0.7, 1.0, 1.0),
+            },
+        ),
+        EditorTool::Kid => (SPRITE_KID1, 0.35, white),
+        EditorTool::JackOLantern => (SPRITE_JACK_O_LANTERN, 0.15, white),
+        EditorTool::StreetLight => (SPRITE_WHITE, 0.08, glm::vec4(0.85, 0.9, 1.0, 1.0)),
+        EditorTool::PlayerSpawn => (SPRITE_OURKID, 0.35, white),
+        EditorTool::Parent => (SPRITE_GRANNY, 0.4, adult_color(AdultKind::Parent)),
+        EditorTool::Police => (SPRITE_GRANNY, 0.4, adult_color(AdultKind::Police)),
+    };
+    Sprite {
+        texture_index,
+        position,
+        scale: glm::vec2(scale, scale),
+        color,
+        unlit: true,
+        ..Default::default()
+    }
+}
+
+fn item_sprite(level: &Level, item: EditorItem) -> Sprite {
+    match item {
+        EditorItem::House(i) => tool_sprite(
+            EditorTool::House,
+            level.houses[i].position,
+            level.houses[i].kind,
+        ),
+        EditorItem::Kid(i) => {
+            tool_sprite(EditorTool::Kid, level.kids[i].position, HouseKind::Regular)
+        }
+        EditorItem::Adult(i) => {
+            let tool = match level.adults[i].kind {
+                AdultKind::Parent => EditorTool::Parent,
+                AdultKind::Police => EditorTool::Police,
+            };
+            tool_sprite(tool, level.adults[i].position, HouseKind::Regular)
+        }
+        EditorItem::Prop(i) => {
+            let tool = match level.props[i].kind {
+                PropKind::JackOLantern => EditorTool::JackOLantern,
+                PropKind::StreetLight => EditorTool::StreetLight,
+            };
+            tool_sprite(tool, level.props[i].position, HouseKind::Regular)
+        }
+        EditorItem::PlayerSpawn => tool_sprite(
+            EditorTool::PlayerSpawn,
+            level.player_spawn,
+            HouseKind::Regular,
+        ),
+    }
+}
+
+pub fn editor_render(galaxy: &Galaxy) {
+    tile_map_render_update(galaxy);
+
+    let editor = galaxy
+        .get_resource::<Editor, _>(Editor::single_resource())
+        .unwrap();
+    let camera = editor.camera;
+
+    if editor.snap {
+        let first = |c: f32| ((c - 1.0) / GRID_LINE_STEP).ceil() as i32;
+        for i in first(camera.x)..=first(camera.x) + (2.0 / GRID_LINE_STEP) as i32 {
+            let x = i as f32 * GRID_LINE_STEP;
+            draw_line(
+                galaxy,
+                glm::vec2(x, camera.y - 1.0),
+                glm::vec2(x, camera.y + 1.0),
+                GRID_COLOR,
+            );
+        }
+        for i in first(camera.y)..=first(camera.y) + (2.0 / GRID_LINE_STEP) as i32 {
+            let y = i as f32 * GRID_LINE_STEP;
+            draw_line(
+                galaxy,
+                glm::vec2(camera.x - 1.0, y),
+                glm::vec2(camera.x + 1.0, y),
+                GRID_COLOR,
+            );
+        }
+    }
+
+    let bounds = editor.level.bounds;
+    let corners = [
+        bounds.min,
+        glm::vec2(bounds.max.x, bounds.min.y),
+        bounds.max,
+        glm::vec2(bounds.min.x, bounds.max.y),
+    ];
+    for i in 0..corners.len() {
+        draw_line(
+            galaxy,
+            corners[i],
+            corners[(i + 1) % corners.len()],
+            BOUNDS_COLOR,
+        );
+    }
+
+    for item in editor.items() {
+        let mut sprite = item_sprite(&editor.level, item);
+        if editor.selected == Some(item) {
+            sprite.color = SELECTED_COLOR;
+        }
+        galaxy.insert_event(RendererDrawSprite(sprite));
+    }
+    // Patrols can't be edited here yet, but show where they go.
+    for kid in &editor.level.kids {
+        for pair in kid.patrol.windows(2) {
+            draw_line(galaxy, pair[0], pair[1], GRID_COLOR);
+        }
+    }
+    // Adults walk theirs round and round, from wherever they start.
+    for adult in &editor.level.adults {
+        let Some(&first) = adult.patrol.first() else {
+            continue;
+        };
+        draw_line(galaxy, adult.position, first, ADULT_PATROL_COLOR);
+        for (i, &point) in adult.patrol.iter().enumerate() {
+            let next = adult.patrol[(i + 1) % adult.patrol.len()];
+            draw_line(galaxy, point, next, ADULT_PATROL_COLOR);
+        }
+    }
+
+    // A preview of what a click would place.
+    let position = editor.cursor_world(galaxy);
+    if !editor.dragging && editor.pick(position).is_none() {
+        let mut sprite = tool_sprite(editor.tool, position, HouseKind::Regular);
+        sprite.color.w = GHOST_ALPHA;
+        galaxy.insert_event(RendererDrawSprite(sprite));
+    }
+
+    draw_text(
+        galaxy,
+        &format!(
+            "EDITOR {}  {}  SNAP {}",
+            editor.file,
+            editor.tool.label(),
+            if editor.snap { "ON" } else { "OFF" }
+        ),
+        camera + TITLE_POSITION,
+        HUD_SIZE,
+        HUD_COLOR,
+    );
+    draw_text(
+        galaxy,
+        &editor.status,
+        camera + STATUS_POSITION,
+        HUD_SIZE,
+        HUD_COLOR,
+    );
+}
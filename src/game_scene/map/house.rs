@@ -1,75 +1,111 @@
 use super::*;
 
-const INTERACT_DISTANCE: f32 = 0.5;
-const HOUSE_COOLDOWN: Duration = Duration::from_secs(30);
+const PORCH_LIGHT_OFFSET: glm::Vec2 = glm::Vec2::new(0.0, -0.2);
+/// Where kids wait to knock, just off the front of the house.
+const DOOR_OFFSET: glm::Vec2 = glm::Vec2::new(0.0, -0.45);
+/// Half the width of the solid part of the house sprite.
+pub const HOUSE_HALF_SIZE: f32 = 0.3;
 
-#[derive(CheapComponent, Clone, Copy)]
+#[derive(CheapComponent, Clone, Copy, Serialize, Deserialize)]
 pub struct House {
     pub position: glm::Vec2,
-    last_entered: Option<Instant>,
+    pub kind: HouseKind,
+    last_entered: Option<GameInstant>,
 }
 
 impl House {
     pub fn as_sprite(&self) -> Sprite {
         Sprite {
-            visible: 1.0,
             texture_index: SPRITE_HOUSE,
             position: self.position,
             scale: glm::vec2(0.7, 0.7),
-            color: glm::vec3(1.0, 1.0, 1.0),
+            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
             ..Default::default()
         }
     }
-}
 
-pub fn house_init(galaxy: &Galaxy) {
-    for y in -4..4 {
-        for x in -4..4 {
-            galaxy.insert_entity().insert(House {
-                position: glm::vec2(x as f32 * 1.4, y as f32 * 1.7 + 1.0),
-                last_entered: None,
-            });
+    pub fn door(&self) -> glm::Vec2 {
+        self.position + DOOR_OFFSET
+    }
+
+    /// A warm bright porch for generous houses and a cold dim one for stingy ones.
+    pub fn as_light(&self) -> Light {
+        let (radius, color, intensity) = match self.kind {
+            HouseKind::Regular => (0.9, glm::vec3(1.0, 0.75, 0.4), 0.9),
+            HouseKind::Generous => (1.1, glm::vec3(1.0, 0.65, 0.3), 1.1),
+            HouseKind::Stingy => (0.7, glm::vec3(0.6, 0.7, 1.0), 0.6),
+        };
+        Light {
+            position: self.position + PORCH_LIGHT_OFFSET,
+            radius,
+            color,
+            intensity,
         }
     }
 }
 
+pub fn house_init(galaxy: &Galaxy, houses: &[LevelHouse]) {
+    for house in houses {
+        galaxy.insert_entity().insert(House {
+            position: house.position,
+            kind: house.kind,
+            last_entered: None,
+        });
+    }
+}
+
 pub fn house_render_update(galaxy: &Galaxy) {
     for house in galaxy.query::<&House>().iter() {
         galaxy.insert_event(RendererDrawSprite(house.as_sprite()));
+        galaxy.insert_event(RendererDrawLight(house.as_light()));
     }
 }
 
 pub fn house_interact_update(galaxy: &Galaxy) {
-    let mut next_state = false;
+    let now = game_now(galaxy);
+    let keys = key_bindings(galaxy);
+    let cooldown = rules(galaxy).house_cooldown;
+    let tuning = tuning(galaxy);
+    let interact_distance = tuning.house.interact_distance;
+    let refuse = notoriety(galaxy).houses_refuse(&tuning.notoriety);
+    let mut entered = None;
 
-    for ev in galaxy.get_events::<WindowEvent>() {
-        if let WindowEventData::KeyboardInput { input, .. } = ev.0 {
-            if input.virtual_keycode == Some(VirtualKeyCode::E)
-                && input.state == ElementState::Released
-            {
-                let player = galaxy
-                    .get_resource::<Player, _>(Player::single_resource())
-                    .unwrap();
+    for input in keyboard_inputs(galaxy) {
+        if input.virtual_keycode == Some(keys.interact) && input.state == ElementState::Released {
+            let player = galaxy
+                .get_resource::<Player, _>(Player::single_resource())
+                .unwrap();
+
+            let index = galaxy
+                .get_resource::<SpatialIndex, _>(SpatialIndex::single_resource())
+                .unwrap();
 
-                for house in galaxy.query::<&mut House>().iter() {
-                    if glm::distance(&player.position, &house.position) < INTERACT_DISTANCE {
-                        if let Some(last_entered) = house.last_entered {
-                            if Instant::now().duration_since(last_entered) < HOUSE_COOLDOWN {
-                                audio_no(galaxy);
-                                continue;
-                            }
-                        }
-                        next_state = true;
-                        house.last_entered = Some(Instant::now());
+            let mut houses = galaxy.query::<&mut House>().iter().collect::<Vec<_>>();
+            for i in index
+                .houses
+                .query_radius(player.position, interact_distance)
+            {
+                let house = &mut *houses[i];
+                if refuse {
+                    audio_no(galaxy);
+                    continue;
+                }
+                if let Some(last_entered) = house.last_entered {
+                    if now.duration_since(last_entered) < cooldown {
+                        audio_no(galaxy);
+                        continue;
                     }
                 }
+                entered = Some(house.kind);
+                house.last_entered = Some(now);
             }
         }
     }
 
-    if next_state {
-        **galaxy
-            .get_mut_resource::<GameState, _>(GameState::single_resource())
-            .unwrap() = GameState::CandyLand;
+    let Some(kind) = entered else {
+        return;
+    };
+    if transition_to(galaxy, TransitionKind::Door, GameState::CandyLand) {
+        candyland::candyland_enter_house(galaxy, kind);
     }
 }
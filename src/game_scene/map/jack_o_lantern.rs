@@ -0,0 +1,71 @@
+use super::*;
+
+const FLICKER_SPEED: f32 = 11.0;
+
+#[derive(CheapComponent, Clone, Copy)]
+pub struct JackOLantern {
+    position: glm::Vec2,
+    lit: GameInstant,
+    phase: f32,
+}
+
+impl JackOLantern {
+    pub fn as_sprite(&self) -> Sprite {
+        Sprite {
+            texture_index: SPRITE_JACK_O_LANTERN,
+            position: self.position,
+            scale: glm::vec2(0.15, 0.15),
+            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
+            // Already glowing, the porch light would only wash it out.
+            unlit: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn as_light(&self, now: GameInstant) -> Light {
+        let t = now.duration_since(self.lit).as_secs_f32();
+        let flicker = (t * FLICKER_SPEED + self.phase).sin() * 0.5
+            + (t * FLICKER_SPEED * 2.3 + self.phase).sin() * 0.25;
+        Light {
+            position: self.position,
+            radius: 0.45,
+            color: glm::vec3(1.0, 0.5, 0.1),
+            intensity: 0.8 + flicker * 0.15,
+        }
+    }
+}
+
+pub fn jack_o_lantern_spawn(galaxy: &Galaxy, rng: &mut StdRng, position: glm::Vec2) {
+    galaxy.insert_entity().insert(JackOLantern {
+        position,
+        lit: game_now(galaxy),
+        phase: rng.gen::<f32>() * std::f32::consts::TAU,
+    });
+}
+
+pub fn jack_o_lantern_render_update(galaxy: &Galaxy) {
+    let now = game_now(galaxy);
+    for lantern in galaxy.query::<&JackOLantern>().iter() {
+        galaxy.insert_event(RendererDrawSprite(lantern.as_sprite()));
+        galaxy.insert_event(RendererDrawLight(lantern.as_light(now)));
+    }
+}
+
+pub fn jack_o_lantern_texture() -> TextureData {
+    TextureData::from_fn(32, 32, |x, y| {
+        let body = (x / 0.95).powi(2) + (y / 0.8).powi(2) < 1.0;
+        let stem = x.abs() < 0.1 && y > 0.7 && y < 0.95;
+        let eye = |cx: f32| y > 0.05 && y < 0.35 && (x - cx).abs() < (0.35 - y) * 0.6;
+        let mouth = y < -0.2 && y > -0.5 && x.abs() < 0.55 && ((x * 8.0).sin() > -0.6 || y > -0.4);
+
+        if stem {
+            [60, 120, 30, 255]
+        } else if body && (eye(-0.35) || eye(0.35) || mouth) {
+            [255, 230, 90, 255]
+        } else if body {
+            [230, 110, 20, 255]
+        } else {
+            [0, 0, 0, 0]
+        }
+    })
+}
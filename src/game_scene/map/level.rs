@@ -0,0 +1,248 @@
+//! Map layouts, either hand authored in `assets/levels` or generated from a seed.
+//! Play a hand authored one with `--level <name>`.
+
+use super::*;
+
+/// Levels that ship with the game.
+const LEVELS: [(&str, &str); 1] = [(
+    "classic",
+    include_str!("../../../assets/levels/classic.ron"),
+)];
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HouseKind {
+    #[default]
+    Regular,
+    /// Hands out candy without much fuss.
+    Generous,
+    /// Notices missing candy a lot sooner.
+    Stingy,
+}
+
+impl HouseKind {
+    pub fn anger_scale(&self) -> f64 {
+        match self {
+            HouseKind::Regular => 1.0,
+            HouseKind::Generous => 0.5,
+            HouseKind::Stingy => 2.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct LevelHouse {
+    pub position: glm::Vec2,
+    #[serde(default)]
+    pub kind: HouseKind,
+}
+
+/// Kids without a patrol wander between houses.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LevelKid {
+    pub position: glm::Vec2,
+    #[serde(default)]
+    pub patrol: Vec<glm::Vec2>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PropKind {
+    JackOLantern,
+    StreetLight,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct LevelProp {
+    pub kind: PropKind,
+    pub position: glm::Vec2,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdultKind {
+    /// Comes running when kids call for help.
+    Parent,
+    /// Sees further and runs faster.
+    Police,
+}
+
+/// Adults walk their patrol in a loop, or stand watch where they are without one.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LevelAdult {
+    pub kind: AdultKind,
+    pub position: glm::Vec2,
+    #[serde(default)]
+    pub patrol: Vec<glm::Vec2>,
+}
+
+/// Tile layers, written as rows of characters with the top row first, see `TileId`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelTiles {
+    /// Bottom left corner of the bottom left tile.
+    pub origin: glm::Vec2,
+    pub tile_size: f32,
+    pub ground: Vec<String>,
+    /// Drawn over the ground, with spaces left see-through.
+    pub decoration: Vec<String>,
+}
+
+#[derive(SingleResource, Clone, Serialize, Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub bounds: LevelBounds,
+    pub player_spawn: glm::Vec2,
+    pub houses: Vec<LevelHouse>,
+    /// Spawn points, reused in order when the rules ask for more kids than there are.
+    #[serde(default)]
+    pub kids: Vec<LevelKid>,
+    #[serde(default)]
+    pub adults: Vec<LevelAdult>,
+    #[serde(default)]
+    pub props: Vec<LevelProp>,
+    #[serde(default)]
+    pub tiles: LevelTiles,
+}
+
+/// Where the current level came from, so saves can bring it back.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum LevelSource {
+    Generated(u64),
+    File(String),
+}
+
+impl Level {
+    fn parse(data: &str) -> Result<Self, String> {
+        let level = ron::from_str::<Level>(data).map_err(|e| e.to_string())?;
+        level.validate()?;
+        Ok(level)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors = vec![];
+        let bounds = self.bounds;
+        let mut check_inside = |position: glm::Vec2, name: &str| {
+            if !bounds.contains(position) {
+                errors.push(format!("{} is outside the bounds", name));
+            }
+        };
+
+        check_inside(self.player_spawn, "player_spawn");
+        for (i, house) in self.houses.iter().enumerate() {
+            check_inside(house.position, &format!("houses[{}]", i));
+        }
+        for (i, kid) in self.kids.iter().enumerate() {
+            check_inside(kid.position, &format!("kids[{}]", i));
+            for (j, &point) in kid.patrol.iter().enumerate() {
+                check_inside(point, &format!("kids[{}].patrol[{}]", i, j));
+            }
+        }
+        for (i, adult) in self.adults.iter().enumerate() {
+            check_inside(adult.position, &format!("adults[{}]", i));
+            for (j, &point) in adult.patrol.iter().enumerate() {
+                check_inside(point, &format!("adults[{}].patrol[{}]", i, j));
+            }
+        }
+        for (i, prop) in self.props.iter().enumerate() {
+            check_inside(prop.position, &format!("props[{}]", i));
+        }
+
+        let rows = self.tiles.ground.iter().chain(&self.tiles.decoration);
+        if rows.clone().next().is_some() && self.tiles.tile_size <= 0.0 {
+            errors.push("tiles.tile_size must be positive".to_string());
+        }
+        for c in rows.flat_map(|row| row.chars()) {
+            if c != ' ' && TileId::from_char(c).is_none() {
+                errors.push(format!("tiles has an unknown tile '{}'", c));
+                break;
+            }
+        }
+
+        if bounds.min.x >= bounds.max.x || bounds.min.y >= bounds.max.y {
+            errors.push("bounds.min must be below bounds.max".to_string());
+        }
+        if self.houses.is_empty() {
+            errors.push("houses must not be empty".to_string());
+        }
+        if self.kids.is_empty() {
+            errors.push("kids must not be empty".to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join(", "))
+        }
+    }
+
+    /// Debug builds on desktop read levels from the source tree, so edits show up without a
+    /// rebuild. Everything else only has the levels built into the game.
+    fn load(name: &str) -> Result<Self, String> {
+        #[cfg(all(
+            debug_assertions,
+            not(all(target_arch = "wasm32", target_os = "unknown"))
+        ))]
+        {
+            let path = format!("{}/assets/levels/{}.ron", env!("CARGO_MANIFEST_DIR"), name);
+            if let Ok(data) = std::fs::read_to_string(&path) {
+                return Level::parse(&data).map_err(|e| format!("{}: {}", path, e));
+            }
+        }
+
+        let (_, data) = LEVELS
+            .iter()
+            .find(|(level, _)| *level == name)
+            .ok_or_else(|| format!("No level called {}", name))?;
+        Level::parse(data).map_err(|e| format!("{}: {}", name, e))
+    }
+}
+
+/// The level for a new run: the one passed with `--level`, or a fresh neighborhood.
+pub fn level_source(galaxy: &Galaxy) -> LevelSource {
+    match cli_arg("--level").flatten() {
+        Some(name) => LevelSource::File(name),
+        None => LevelSource::Generated(rng_seed(galaxy)),
+    }
+}
+
+/// The neighborhood's `source` is the one actually built, which is a generated one when a
+/// level file fails to load, so saves come back to the level that was played.
+fn level_build(galaxy: &Galaxy, source: LevelSource) -> (Neighborhood, Level) {
+    let rules = rules(galaxy);
+    let tuning = tuning(galaxy);
+    let seed = match source {
+        LevelSource::Generated(seed) => seed,
+        LevelSource::File(name) => match Level::load(&name) {
+            Ok(level) => return (Neighborhood::empty(LevelSource::File(name)), level),
+            Err(e) => {
+                eprintln!("Generating a neighborhood instead: {}", e);
+                rng_seed(galaxy)
+            }
+        },
+    };
+    Neighborhood::generate(seed, rules.kid_count, tuning.house.lantern_chance)
+}
+
+pub fn level_init(galaxy: &Galaxy) {
+    let (neighborhood, level) = level_build(galaxy, level_source(galaxy));
+    galaxy.insert_resource(Neighborhood::single_resource(), neighborhood);
+    galaxy.insert_resource(Level::single_resource(), level);
+    tile_map_init(galaxy);
+}
+
+/// Swap in the level from `source`. Nothing gets spawned, see `map_populate`.
+pub fn level_load(galaxy: &Galaxy, source: LevelSource) {
+    let (neighborhood, level) = level_build(galaxy, source);
+    **galaxy
+        .get_mut_resource::<Neighborhood, _>(Neighborhood::single_resource())
+        .unwrap() = neighborhood;
+    **galaxy
+        .get_mut_resource::<Level, _>(Level::single_resource())
+        .unwrap() = level;
+    tile_map_rebuild(galaxy);
+}
+
+pub fn level(galaxy: &Galaxy) -> Level {
+    galaxy
+        .get_resource::<Level, _>(Level::single_resource())
+        .unwrap()
+        .clone()
+}
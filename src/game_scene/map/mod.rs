@@ -2,38 +2,234 @@ use super::*;
 
 mod bounds;
 mod candy;
+#[cfg(debug_assertions)]
+mod editor;
 mod house;
+mod jack_o_lantern;
+mod level;
+mod navigation;
+mod neighborhood;
+mod npc_adult;
 mod npc_bully;
 mod npc_generic;
+mod npc_mind;
+mod npc_watch;
 mod player;
+mod prop;
+mod spatial;
+mod steering;
+mod tile_map;
 
 use bounds::*;
 use candy::*;
 use house::*;
+use jack_o_lantern::*;
+use level::*;
+use navigation::*;
+use neighborhood::*;
+use npc_adult::*;
 use npc_generic::*;
+use npc_mind::*;
+use npc_watch::*;
 use player::*;
+use prop::*;
+use spatial::*;
+use steering::*;
+use tile_map::*;
 
+#[cfg(debug_assertions)]
+pub use editor::{editor_enter, editor_init, editor_render, editor_update};
+pub use jack_o_lantern::jack_o_lantern_texture;
+pub use level::HouseKind;
+
+/// Debug tools keep these keys to themselves.
+#[cfg(debug_assertions)]
+pub const DEBUG_KEYS: [VirtualKeyCode; 2] = [editor::EDITOR_KEY, cone_debug::CONE_DEBUG_KEY];
 pub use player::Player;
+pub use tile_map::tile_atlas_texture;
+
+const NIGHT_AMBIENT: glm::Vec3 = glm::Vec3::new(0.22, 0.2, 0.38);
 
 pub fn map_init(galaxy: &Galaxy) {
     player_init(galaxy);
-    npc_generic_init(galaxy);
-    house_init(galaxy);
+    level_init(galaxy);
+    nav_grid_init(galaxy);
+    spatial_index_init(galaxy);
+    #[cfg(debug_assertions)]
+    cone_debug::cone_debug_init(galaxy);
+    map_populate(galaxy);
+
+    spawn_particle_emitter(
+        galaxy,
+        GameState::Map,
+        glm::vec2(0.0, 1.3),
+        ParticleEmitterConfig::autumn_leaves(),
+    );
+}
+
+/// Fill the map with everything the current level places on it.
+fn map_populate(galaxy: &Galaxy) {
+    let level = level(galaxy);
+    npc_generic_init(galaxy, &level.kids, rules(galaxy).kid_count);
+    npc_adult_init(galaxy, &level.adults);
+    house_init(galaxy, &level.houses);
+    nav_grid_rebuild(
+        galaxy,
+        &level
+            .houses
+            .iter()
+            .map(|house| house.position)
+            .collect::<Vec<_>>(),
+    );
+    prop_init(galaxy, &level.props);
+    player_spawn(galaxy, level.player_spawn);
+}
+
+fn map_clear(galaxy: &Galaxy) {
+    for (e, _) in galaxy.query::<&House>().eiter() {
+        galaxy.remove_entity(e);
+    }
+    for (e, _) in galaxy.query::<&NpcGeneric>().eiter() {
+        galaxy.remove_entity(e);
+    }
+    for (e, _) in galaxy.query::<&Candy>().eiter() {
+        galaxy.remove_entity(e);
+    }
+    for (e, _) in galaxy.query::<&JackOLantern>().eiter() {
+        galaxy.remove_entity(e);
+    }
+    for (e, _) in galaxy.query::<&StreetLight>().eiter() {
+        galaxy.remove_entity(e);
+    }
+    for (e, _) in galaxy.query::<&Adult>().eiter() {
+        galaxy.remove_entity(e);
+    }
+    for (e, _) in galaxy.query::<&NeighborhoodWatch>().eiter() {
+        galaxy.remove_entity(e);
+    }
+}
+
+/// Tear down everything spawned on the map and load the level for a new run.
+pub fn map_reset(galaxy: &Galaxy) {
+    map_clear(galaxy);
+    level_load(galaxy, level_source(galaxy));
+    map_populate(galaxy);
+}
+
+/// Everything on the map worth keeping between sessions.
+/// Props are decoration and come back from the level on restore, and the neighborhood
+/// watch comes back out to match the notoriety.
+#[derive(Serialize, Deserialize)]
+pub struct MapSnapshot {
+    /// The level is loaded again from this.
+    level: LevelSource,
+    player: Player,
+    houses: Vec<House>,
+    npcs: Vec<NpcGeneric>,
+    adults: Vec<Adult>,
+    candy: Vec<Candy>,
+}
+
+pub fn map_snapshot(galaxy: &Galaxy) -> MapSnapshot {
+    MapSnapshot {
+        level: galaxy
+            .get_resource::<Neighborhood, _>(Neighborhood::single_resource())
+            .unwrap()
+            .source
+            .clone(),
+        player: *galaxy
+            .get_resource::<Player, _>(Player::single_resource())
+            .unwrap(),
+        houses: galaxy.query::<&House>().iter().copied().collect(),
+        npcs: galaxy.query::<&NpcGeneric>().iter().copied().collect(),
+        adults: galaxy.query::<&Adult>().iter().copied().collect(),
+        candy: galaxy.query::<&Candy>().iter().copied().collect(),
+    }
+}
+
+pub fn map_restore(galaxy: &Galaxy, snapshot: MapSnapshot) {
+    map_clear(galaxy);
+    level_load(galaxy, snapshot.level);
+
+    **galaxy
+        .get_mut_resource::<Player, _>(Player::single_resource())
+        .unwrap() = snapshot.player;
+
+    nav_grid_rebuild(
+        galaxy,
+        &snapshot
+            .houses
+            .iter()
+            .map(|house| house.position)
+            .collect::<Vec<_>>(),
+    );
+    for house in snapshot.houses {
+        galaxy.insert_entity().insert(house);
+    }
+    for npc in snapshot.npcs {
+        galaxy.insert_entity().insert(npc);
+    }
+    for adult in snapshot.adults {
+        galaxy.insert_entity().insert(adult);
+    }
+    for candy in snapshot.candy {
+        galaxy.insert_entity().insert(candy);
+    }
+    prop_init(galaxy, &level(galaxy).props);
 }
 
 pub fn map_update(galaxy: &Galaxy) {
+    let keys = key_bindings(galaxy);
+    for input in keyboard_inputs(galaxy) {
+        let Some(key) = input.virtual_keycode else {
+            continue;
+        };
+        // Escape can't be bound to anything else, see `KeyBindings`.
+        if (key == keys.pause || key == VirtualKeyCode::Escape)
+            && input.state == ElementState::Released
+        {
+            pause_open(galaxy, PausePage::Main);
+            return;
+        }
+        #[cfg(debug_assertions)]
+        if key == editor::EDITOR_KEY && input.state == ElementState::Released {
+            state_set(galaxy, GameState::Editor);
+            return;
+        }
+        #[cfg(debug_assertions)]
+        if key == cone_debug::CONE_DEBUG_KEY && input.state == ElementState::Released {
+            cone_debug::cone_debug_toggle(galaxy);
+        }
+    }
+
+    spatial_index_update(galaxy);
     house_interact_update(galaxy);
-    house_render_update(galaxy);
 
     npc_generic_punch_update(galaxy);
     npc_generic_update(galaxy);
-    npc_generic_render_update(galaxy);
+    npc_watch_update(galaxy);
+    npc_adult_update(galaxy);
 
     candy_movement_update(galaxy);
     candy_in_range_pickup_update(galaxy);
     candy_in_range_movement_update(galaxy);
-    candy_render_update(galaxy);
 
     player_movement_update(galaxy);
+    bounds_check_update(galaxy);
+}
+
+pub fn map_render(galaxy: &Galaxy) {
+    galaxy.insert_event(RendererAmbientLight(NIGHT_AMBIENT));
+
+    tile_map_render_update(galaxy);
+    house_render_update(galaxy);
+    prop_render_update(galaxy);
+    npc_generic_render_update(galaxy);
+    npc_watch_render_update(galaxy);
+    npc_adult_render_update(galaxy);
+    #[cfg(debug_assertions)]
+    cone_debug::cone_debug_render_update(galaxy);
+    candy_render_update(galaxy);
     player_render_update(galaxy);
+    neighborhood_name_render(galaxy);
 }
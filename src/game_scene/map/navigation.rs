@@ -0,0 +1,410 @@
+use super::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+const NAV_CELL_SIZE: f32 = 0.175;
+/// Room kept between a path and the walls of a house. Kids can be off a path by up to half a
+/// cell diagonally, so this needs to cover that.
+const NAV_CLEARANCE: f32 = 0.13;
+/// Close enough to a waypoint to start on the next one.
+const WAYPOINT_RADIUS: f32 = 0.08;
+/// Cached paths are all dropped once there are this many, they're cheap to find again.
+const PATH_CACHE_LIMIT: usize = 512;
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+type Cell = (u16, u16);
+
+/// A path from one grid cell to another. Kids keep this rather than the path itself,
+/// which lives in the `NavGrid` cache and gets found again if it was dropped.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NavRoute {
+    from: Cell,
+    to: Cell,
+    waypoint: usize,
+}
+
+/// Which parts of the level can be walked, for finding paths around houses.
+#[derive(SingleResource, Default)]
+pub struct NavGrid {
+    origin: glm::Vec2,
+    width: usize,
+    height: usize,
+    houses: Vec<glm::Vec2>,
+    blocked: Vec<bool>,
+    cache: HashMap<(Cell, Cell), Vec<glm::Vec2>>,
+}
+
+impl NavGrid {
+    pub fn new(bounds: LevelBounds, houses: &[glm::Vec2]) -> Self {
+        let size = bounds.max - bounds.min;
+        let width = (size.x / NAV_CELL_SIZE).ceil().max(1.0) as usize;
+        let height = (size.y / NAV_CELL_SIZE).ceil().max(1.0) as usize;
+        let mut grid = NavGrid {
+            origin: bounds.min,
+            width,
+            height,
+            houses: houses.to_vec(),
+            blocked: vec![],
+            cache: HashMap::new(),
+        };
+        grid.blocked = (0..width * height)
+            .map(|i| !grid.is_clear(grid.cell_center(((i % width) as u16, (i / width) as u16))))
+            .collect();
+        grid
+    }
+
+    fn is_clear(&self, point: glm::Vec2) -> bool {
+        let reach = HOUSE_HALF_SIZE + NAV_CLEARANCE;
+        !self
+            .houses
+            .iter()
+            .any(|house| (point.x - house.x).abs() < reach && (point.y - house.y).abs() < reach)
+    }
+
+    /// Whether nothing blocks the view from `from` to `to`. Unlike walking past, looking past
+    /// can graze the walls of a house.
+    pub fn can_see(&self, from: glm::Vec2, to: glm::Vec2) -> bool {
+        !self
+            .houses
+            .iter()
+            .any(|&house| segment_hits_box(from, to, house, HOUSE_HALF_SIZE))
+    }
+
+    /// Start walking from `position` to `goal`.
+    pub fn route(&self, position: glm::Vec2, goal: glm::Vec2) -> NavRoute {
+        NavRoute {
+            from: self.cell_of(position),
+            to: self.cell_of(goal),
+            waypoint: 0,
+        }
+    }
+
+    /// Where to head next along `route`, moving on to the next waypoint once this one is reached.
+    /// Ends with `goal` itself, or heads straight for it if there's no way through.
+    pub fn follow(
+        &mut self,
+        route: &mut NavRoute,
+        position: glm::Vec2,
+        goal: glm::Vec2,
+    ) -> glm::Vec2 {
+        let path = self.path(route.from, route.to);
+        while let Some(&waypoint) = path.get(route.waypoint) {
+            if glm::distance(&waypoint, &position) > WAYPOINT_RADIUS {
+                return waypoint;
+            }
+            route.waypoint += 1;
+        }
+        goal
+    }
+
+    fn path(&mut self, from: Cell, to: Cell) -> &[glm::Vec2] {
+        if !self.cache.contains_key(&(from, to)) {
+            if self.cache.len() >= PATH_CACHE_LIMIT {
+                self.cache.clear();
+            }
+            let path = self.find_path(from, to);
+            self.cache.insert((from, to), path);
+        }
+        &self.cache[&(from, to)]
+    }
+
+    fn cell_of(&self, position: glm::Vec2) -> Cell {
+        let local = (position - self.origin) / NAV_CELL_SIZE;
+        (
+            (local.x.max(0.0) as usize).min(self.width - 1) as u16,
+            (local.y.max(0.0) as usize).min(self.height - 1) as u16,
+        )
+    }
+
+    fn cell_center(&self, (x, y): Cell) -> glm::Vec2 {
+        self.origin + glm::vec2(x as f32 + 0.5, y as f32 + 0.5) * NAV_CELL_SIZE
+    }
+
+    fn index(&self, (x, y): Cell) -> usize {
+        y as usize * self.width + x as usize
+    }
+
+    fn is_blocked(&self, (x, y): (i32, i32)) -> bool {
+        x < 0
+            || y < 0
+            || x as usize >= self.width
+            || y as usize >= self.height
+            || self.blocked[y as usize * self.width + x as usize]
+    }
+
+    /// Kids knocked into a house still need a way out of it.
+    fn nearest_open(&self, cell: Cell) -> Option<Cell> {
+        if !self.blocked[self.index(cell)] {
+            return Some(cell);
+        }
+        (0..self.width * self.height)
+            .filter(|&i| !self.blocked[i])
+            .map(|i| ((i % self.width) as u16, (i / self.width) as u16))
+            .min_by_key(|&(x, y)| x.abs_diff(cell.0) as u32 + y.abs_diff(cell.1) as u32)
+    }
+
+    /// A* over the grid, smoothed into as few straight runs as possible.
+    /// Empty when there's no way through.
+    fn find_path(&self, start: Cell, to: Cell) -> Vec<glm::Vec2> {
+        let (Some(from), Some(to)) = (self.nearest_open(start), self.nearest_open(to)) else {
+            return vec![];
+        };
+        let heuristic = |(x, y): Cell| {
+            let dx = x.abs_diff(to.0) as u32;
+            let dy = y.abs_diff(to.1) as u32;
+            STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+        };
+
+        let mut cost = vec![u32::MAX; self.width * self.height];
+        let mut came_from = vec![usize::MAX; self.width * self.height];
+        let mut open = BinaryHeap::new();
+        cost[self.index(from)] = 0;
+        open.push(Reverse((heuristic(from), self.index(from))));
+
+        while let Some(Reverse((_, current))) = open.pop() {
+            let cell = ((current % self.width) as u16, (current / self.width) as u16);
+            if cell == to {
+                break;
+            }
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if dx == 0 && dy == 0 {
+                        continue;
+                    }
+                    let (x, y) = (cell.0 as i32 + dx, cell.1 as i32 + dy);
+                    // No cutting corners past a house.
+                    if self.is_blocked((x, y))
+                        || self.is_blocked((cell.0 as i32 + dx, cell.1 as i32))
+                        || self.is_blocked((cell.0 as i32, cell.1 as i32 + dy))
+                    {
+                        continue;
+                    }
+                    let next = (x as u16, y as u16);
+                    let step = if dx != 0 && dy != 0 {
+                        DIAGONAL_COST
+                    } else {
+                        STRAIGHT_COST
+                    };
+                    let next_cost = cost[current] + step;
+                    let i = self.index(next);
+                    if next_cost < cost[i] {
+                        cost[i] = next_cost;
+                        came_from[i] = current;
+                        open.push(Reverse((next_cost + heuristic(next), i)));
+                    }
+                }
+            }
+        }
+
+        if cost[self.index(to)] == u32::MAX {
+            return vec![];
+        }
+        let mut cells = vec![self.index(to)];
+        while *cells.last().unwrap() != self.index(from) {
+            cells.push(came_from[*cells.last().unwrap()]);
+        }
+        cells.reverse();
+
+        let points = cells
+            .into_iter()
+            .map(|i| self.cell_center(((i % self.width) as u16, (i / self.width) as u16)))
+            .collect::<Vec<_>>();
+        let mut path = self.smooth(&points);
+        // Step out into the open first, rather than cutting across the house from inside it.
+        if from != start {
+            path.insert(0, points[0]);
+        }
+        path
+    }
+
+    /// Skip every point that can be walked past in a straight line.
+    fn smooth(&self, points: &[glm::Vec2]) -> Vec<glm::Vec2> {
+        let mut smoothed = vec![];
+        let mut anchor = 0;
+        while anchor + 1 < points.len() {
+            let mut next = anchor + 1;
+            while next + 1 < points.len() && self.line_of_sight(points[anchor], points[next + 1]) {
+                next += 1;
+            }
+            smoothed.push(points[next]);
+            anchor = next;
+        }
+        smoothed
+    }
+
+    /// Whether a straight walk from `from` to `to` stays clear of every house.
+    pub fn line_of_sight(&self, from: glm::Vec2, to: glm::Vec2) -> bool {
+        let steps = (glm::distance(&from, &to) / (NAV_CELL_SIZE * 0.25)).ceil() as usize;
+        (0..=steps).all(|i| self.is_clear(glm::lerp(&from, &to, i as f32 / steps.max(1) as f32)))
+    }
+}
+
+/// Clip the segment against each axis of the box in turn, missing once nothing is left.
+fn segment_hits_box(from: glm::Vec2, to: glm::Vec2, center: glm::Vec2, half_size: f32) -> bool {
+    let direction = to - from;
+    let (mut enter, mut exit) = (0.0f32, 1.0f32);
+    for axis in 0..2 {
+        let (min, max) = (center[axis] - half_size, center[axis] + half_size);
+        if direction[axis].abs() < f32::EPSILON {
+            if from[axis] < min || from[axis] > max {
+                return false;
+            }
+            continue;
+        }
+        let a = (min - from[axis]) / direction[axis];
+        let b = (max - from[axis]) / direction[axis];
+        enter = enter.max(a.min(b));
+        exit = exit.min(a.max(b));
+        if enter > exit {
+            return false;
+        }
+    }
+    true
+}
+
+pub fn nav_grid_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(NavGrid::single_resource(), NavGrid::default());
+}
+
+/// Rebuild around `houses`, which should be everything about to be on the map.
+pub fn nav_grid_rebuild(galaxy: &Galaxy, houses: &[glm::Vec2]) {
+    let grid = NavGrid::new(level(galaxy).bounds, houses);
+    **galaxy
+        .get_mut_resource::<NavGrid, _>(NavGrid::single_resource())
+        .unwrap() = grid;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(extent: glm::Vec2, houses: &[glm::Vec2]) -> NavGrid {
+        NavGrid::new(
+            LevelBounds {
+                min: -extent,
+                max: extent,
+            },
+            houses,
+        )
+    }
+
+    fn inside_house(point: glm::Vec2, houses: &[glm::Vec2]) -> bool {
+        houses.iter().any(|house| {
+            (point.x - house.x).abs() < HOUSE_HALF_SIZE
+                && (point.y - house.y).abs() < HOUSE_HALF_SIZE
+        })
+    }
+
+    /// Walk from `start` to `goal` the way kids do, failing if it takes too long.
+    fn walk(nav: &mut NavGrid, start: glm::Vec2, goal: glm::Vec2) -> Vec<glm::Vec2> {
+        let mut position = start;
+        let mut route = nav.route(start, goal);
+        let mut trail = vec![position];
+        for _ in 0..5000 {
+            if glm::distance(&position, &goal) < 0.01 {
+                return trail;
+            }
+            let target = nav.follow(&mut route, position, goal);
+            let offset = target - position;
+            position += offset * (0.01 / glm::length(&offset)).min(1.0);
+            trail.push(position);
+        }
+        panic!("never got from {:?} to {:?}", start, goal);
+    }
+
+    #[test]
+    fn open_ground_is_one_straight_run() {
+        let mut nav = grid(glm::vec2(2.0, 2.0), &[]);
+        let route = nav.route(glm::vec2(-1.5, -1.2), glm::vec2(1.4, 1.3));
+        assert_eq!(nav.path(route.from, route.to).len(), 1);
+    }
+
+    #[test]
+    fn path_goes_around_a_house() {
+        let houses = [glm::vec2(0.0, 0.0)];
+        let mut nav = grid(glm::vec2(2.0, 2.0), &houses);
+        let (start, goal) = (glm::vec2(-1.5, 0.0), glm::vec2(1.5, 0.0));
+        assert!(!nav.line_of_sight(start, goal));
+
+        let route = nav.route(start, goal);
+        let path = nav.path(route.from, route.to).to_vec();
+        assert!(path.len() >= 2);
+        assert!(path.iter().any(|point| point.y.abs() > HOUSE_HALF_SIZE));
+
+        let trail = walk(&mut nav, start, goal);
+        assert!(trail.iter().all(|&point| !inside_house(point, &houses)));
+    }
+
+    #[test]
+    fn smoothing_never_cuts_a_corner() {
+        let houses = [
+            glm::vec2(-1.0, -1.0),
+            glm::vec2(0.0, -0.2),
+            glm::vec2(1.0, 0.6),
+            glm::vec2(-0.8, 1.1),
+            glm::vec2(0.9, -1.3),
+        ];
+        let mut nav = grid(glm::vec2(2.5, 2.5), &houses);
+        let points = [
+            glm::vec2(-2.2, -2.2),
+            glm::vec2(2.2, 2.2),
+            glm::vec2(-2.2, 2.0),
+            glm::vec2(2.1, -2.2),
+            glm::vec2(0.0, 0.6),
+            glm::vec2(-0.5, -1.6),
+        ];
+        for &from in &points {
+            for &to in points.iter().filter(|&&to| to != from) {
+                let route = nav.route(from, to);
+                let start = nav.cell_center(route.from);
+                let path = nav.path(route.from, route.to).to_vec();
+                assert!(!path.is_empty());
+                for pair in std::iter::once(start)
+                    .chain(path)
+                    .collect::<Vec<_>>()
+                    .windows(2)
+                {
+                    assert!(
+                        nav.line_of_sight(pair[0], pair[1]),
+                        "{:?} to {:?} cuts past a house",
+                        pair[0],
+                        pair[1]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn walled_off_goal_is_headed_straight_for() {
+        // The house reaches past both sides of a level this narrow.
+        let mut nav = grid(glm::vec2(0.35, 2.0), &[glm::vec2(0.0, 0.0)]);
+        let (start, goal) = (glm::vec2(0.0, -1.5), glm::vec2(0.0, 1.5));
+        let mut route = nav.route(start, goal);
+        assert!(nav.path(route.from, route.to).is_empty());
+        assert_eq!(nav.follow(&mut route, start, goal), goal);
+    }
+
+    #[test]
+    fn knocked_into_a_house_steps_out_first() {
+        let houses = [glm::vec2(0.0, 0.0)];
+        let mut nav = grid(glm::vec2(2.0, 2.0), &houses);
+        let route = nav.route(glm::vec2(0.05, 0.05), glm::vec2(1.5, 1.5));
+        let first = nav.path(route.from, route.to)[0];
+        assert!(nav.is_clear(first));
+    }
+
+    #[test]
+    fn houses_block_the_view_but_not_grazing_it() {
+        let nav = grid(glm::vec2(2.0, 2.0), &[glm::vec2(0.0, 0.0)]);
+        assert!(!nav.can_see(glm::vec2(-1.0, 0.0), glm::vec2(1.0, 0.0)));
+        assert!(!nav.can_see(glm::vec2(-1.0, -1.0), glm::vec2(1.0, 1.0)));
+        assert!(nav.can_see(glm::vec2(-1.0, 0.4), glm::vec2(1.0, 0.4)));
+        assert!(nav.can_see(glm::vec2(-1.0, 0.0), glm::vec2(-0.5, 0.0)));
+        // Along a wall counts as seen past, unlike walking, which keeps its distance.
+        let wall = HOUSE_HALF_SIZE + 0.01;
+        assert!(nav.can_see(glm::vec2(-1.0, wall), glm::vec2(1.0, wall)));
+        assert!(!nav.line_of_sight(glm::vec2(-1.0, wall), glm::vec2(1.0, wall)));
+    }
+}
@@ -0,0 +1,419 @@
+use super::*;
+use std::collections::VecDeque;
+
+/// Houses are two cells across, streets two cells wide.
+pub const CELL_SIZE: f32 = 0.35;
+const GRID_SIZE: usize = (MAP_BOUND_SIZE * 1.25 / CELL_SIZE) as usize;
+const STREET_WIDTH: usize = 2;
+const HOUSE_SIZE: usize = 2;
+/// Space between street lines, street included.
+const BLOCK_SPACING: std::ops::RangeInclusive<usize> = 9..=12;
+const PARK_CHANCE: f64 = 0.15;
+const MAX_PARKS: usize = 2;
+const CUL_DE_SAC_CHANCE: f64 = 0.35;
+const CUL_DE_SAC_MIN_BLOCK: usize = 8;
+const HOUSE_JITTER: f32 = 0.05;
+const GENEROUS_CHANCE: f64 = 0.15;
+const STINGY_CHANCE: f64 = 0.15;
+/// Jack-o'-lanterns sit on the lawn beside the door.
+const LANTERN_OFFSET: glm::Vec2 = glm::Vec2::new(0.25, -0.3);
+const PARENT_COUNT: usize = 2;
+const LEAVES_CHANCE: f64 = 0.06;
+const FLOWERS_CHANCE: f64 = 0.03;
+const NAME_POSITION: glm::Vec2 = glm::Vec2::new(-0.7, -0.9);
+const NAME_SIZE: f32 = 0.045;
+const NAME_COLOR: glm::Vec4 = glm::Vec4::new(0.6, 0.6, 0.6, 1.0);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Lawn,
+    Street,
+    Park,
+    House,
+}
+
+/// The street layout under the current level. Hand authored levels are all lawn.
+#[derive(SingleResource, Clone)]
+pub struct Neighborhood {
+    pub source: LevelSource,
+    tiles: Vec<Tile>,
+}
+
+impl Neighborhood {
+    pub fn empty(source: LevelSource) -> Self {
+        Neighborhood {
+            source,
+            tiles: vec![Tile::Lawn; GRID_SIZE * GRID_SIZE],
+        }
+    }
+
+    /// Lay out streets for `seed` along with the level to go on them.
+    pub fn generate(seed: u64, kid_count: usize, lantern_chance: f64) -> (Self, Level) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut neighborhood = Neighborhood::empty(LevelSource::Generated(seed));
+        let extent = GRID_SIZE as f32 * CELL_SIZE * 0.5;
+        let mut level = Level {
+            name: format!("SEED {}", seed),
+            bounds: LevelBounds {
+                min: glm::vec2(-extent, -extent),
+                max: glm::vec2(extent, extent),
+            },
+            player_spawn: glm::vec2(0.0, 0.0),
+            houses: vec![],
+            kids: vec![],
+            adults: vec![],
+            props: vec![],
+            tiles: LevelTiles::default(),
+        };
+
+        let columns = street_lines(&mut rng);
+        let rows = street_lines(&mut rng);
+        for &x in &columns {
+            neighborhood.fill(x, 0, x + STREET_WIDTH, GRID_SIZE, Tile::Street);
+        }
+        for &y in &rows {
+            neighborhood.fill(0, y, GRID_SIZE, y + STREET_WIDTH, Tile::Street);
+        }
+
+        let mut footprints = vec![];
+        let mut parks = 0;
+        for x in columns.windows(2) {
+            for y in rows.windows(2) {
+                let (x0, x1) = (x[0] + STREET_WIDTH, x[1]);
+                let (y0, y1) = (y[0] + STREET_WIDTH, y[1]);
+                if parks < MAX_PARKS && rng.gen_bool(PARK_CHANCE) {
+                    parks += 1;
+                    neighborhood.fill(x0, y0, x1, y1, Tile::Park);
+                    continue;
+                }
+                if x1 - x0 >= CUL_DE_SAC_MIN_BLOCK
+                    && y1 - y0 >= CUL_DE_SAC_MIN_BLOCK
+                    && rng.gen_bool(CUL_DE_SAC_CHANCE)
+                {
+                    neighborhood.cul_de_sac(&mut rng, x0, y0, x1, y1);
+                }
+                footprints.extend(neighborhood.place_houses(&mut rng, x0, y0, x1, y1));
+            }
+        }
+
+        let (spawn_x, spawn_y) = neighborhood.nearest_street(GRID_SIZE / 2, GRID_SIZE / 2);
+        level.player_spawn = cell_center(spawn_x, spawn_y);
+
+        // Anything walled in by its neighbors goes, so every door can be walked up to.
+        let reachable = neighborhood.reachable_from(spawn_x, spawn_y);
+        for (x, y) in footprints {
+            if neighborhood.footprint_reachable(&reachable, x, y) {
+                let jitter = glm::vec2(
+                    rng.gen_range(-HOUSE_JITTER..=HOUSE_JITTER),
+                    rng.gen_range(-HOUSE_JITTER..=HOUSE_JITTER),
+                );
+                level.houses.push(LevelHouse {
+                    position: footprint_center(x, y) + jitter,
+                    kind: HouseKind::Regular,
+                });
+            } else {
+                neighborhood.fill(x, y, x + HOUSE_SIZE, y + HOUSE_SIZE, Tile::Lawn);
+            }
+        }
+
+        let streets = (0..GRID_SIZE * GRID_SIZE)
+            .filter(|&i| neighborhood.tiles[i] == Tile::Street)
+            .collect::<Vec<_>>();
+        for _ in 0..kid_count.max(1) {
+            let i = streets[rng.gen_range(0..streets.len())];
+            level.kids.push(LevelKid {
+                position: cell_center(i % GRID_SIZE, i / GRID_SIZE),
+                patrol: vec![],
+            });
+        }
+
+        for house in &mut level.houses {
+            let roll = rng.gen::<f64>();
+            house.kind = if roll < GENEROUS_CHANCE {
+                HouseKind::Generous
+            } else if roll < GENEROUS_CHANCE + STINGY_CHANCE {
+                HouseKind::Stingy
+            } else {
+                HouseKind::Regular
+            };
+            if rng.gen_bool(lantern_chance) {
+                level.props.push(LevelProp {
+                    kind: PropKind::JackOLantern,
+                    position: house.position + LANTERN_OFFSET,
+                });
+            }
+        }
+
+        level.tiles = neighborhood.level_tiles(&mut rng);
+
+        // Parents walk around a block, the police around the whole neighborhood.
+        let block = |x0: usize, y0: usize, x1: usize, y1: usize| {
+            vec![
+                street_center(x0, y0),
+                street_center(x1, y0),
+                street_center(x1, y1),
+                street_center(x0, y1),
+            ]
+        };
+        for _ in 0..PARENT_COUNT {
+            let x = rng.gen_range(0..columns.len() - 1);
+            let y = rng.gen_range(0..rows.len() - 1);
+            let patrol = block(columns[x], rows[y], columns[x + 1], rows[y + 1]);
+            level.adults.push(LevelAdult {
+                kind: AdultKind::Parent,
+                position: patrol[0],
+                patrol,
+            });
+        }
+        let patrol = block(
+            columns[0],
+            rows[0],
+            *columns.last().unwrap(),
+            *rows.last().unwrap(),
+        );
+        level.adults.push(LevelAdult {
+            kind: AdultKind::Police,
+            position: patrol[0],
+            patrol,
+        });
+
+        (neighborhood, level)
+    }
+
+    pub fn tile(&self, x: usize, y: usize) -> Tile {
+        self.tiles[y * GRID_SIZE + x]
+    }
+
+    fn fill(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, tile: Tile) {
+        for y in y0..y1.min(GRID_SIZE) {
+            for x in x0..x1.min(GRID_SIZE) {
+                self.tiles[y * GRID_SIZE + x] = tile;
+            }
+        }
+    }
+
+    /// A dead end street running from one side of the block into a turning circle in the middle.
+    fn cul_de_sac(&mut self, rng: &mut StdRng, x0: usize, y0: usize, x1: usize, y1: usize) {
+        let (mid_x, mid_y) = ((x0 + x1) / 2, (y0 + y1) / 2);
+        let half = STREET_WIDTH / 2;
+        match rng.gen_range(0..4) {
+            0 => self.fill(mid_x - half, y0, mid_x + half, mid_y, Tile::Street),
+            1 => self.fill(mid_x - half, mid_y, mid_x + half, y1, Tile::Street),
+            2 => self.fill(x0, mid_y - half, mid_x, mid_y + half, Tile::Street),
+            _ => self.fill(mid_x, mid_y - half, x1, mid_y + half, Tile::Street),
+        }
+        self.fill(
+            mid_x - STREET_WIDTH,
+            mid_y - STREET_WIDTH,
+            mid_x + STREET_WIDTH,
+            mid_y + STREET_WIDTH,
+            Tile::Street,
+        );
+    }
+
+    /// Houses front onto a street, with at least a cell of lawn between neighbors.
+    fn place_houses(
+        &mut self,
+        rng: &mut StdRng,
+        x0: usize,
+        y0: usize,
+        x1: usize,
+        y1: usize,
+    ) -> Vec<(usize, usize)> {
+        let mut candidates = vec![];
+        for y in y0..=y1.saturating_sub(HOUSE_SIZE) {
+            for x in x0..=x1.saturating_sub(HOUSE_SIZE) {
+                if self.footprint_is(x, y, Tile::Lawn) && self.fronts_street(x, y) {
+                    candidates.push((x, y));
+                }
+            }
+        }
+        candidates.shuffle(rng);
+
+        let mut placed = vec![];
+        for (x, y) in candidates {
+            let clear = (x.saturating_sub(1)..x + HOUSE_SIZE + 1).all(|cx| {
+                (y.saturating_sub(1)..y + HOUSE_SIZE + 1).all(|cy| {
+                    cx >= GRID_SIZE || cy >= GRID_SIZE || self.tile(cx, cy) != Tile::House
+                })
+            });
+            if clear && self.footprint_is(x, y, Tile::Lawn) {
+                self.fill(x, y, x + HOUSE_SIZE, y + HOUSE_SIZE, Tile::House);
+                placed.push((x, y));
+            }
+        }
+        placed
+    }
+
+    fn footprint_is(&self, x: usize, y: usize, tile: Tile) -> bool {
+        x + HOUSE_SIZE <= GRID_SIZE
+            && y + HOUSE_SIZE <= GRID_SIZE
+            && (x..x + HOUSE_SIZE).all(|x| (y..y + HOUSE_SIZE).all(|y| self.tile(x, y) == tile))
+    }
+
+    fn footprint_neighbors(x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut neighbors = vec![];
+        for i in 0..HOUSE_SIZE {
+            neighbors.push((x + i, y + HOUSE_SIZE));
+            neighbors.push((x + HOUSE_SIZE, y + i));
+            if y > 0 {
+                neighbors.push((x + i, y - 1));
+            }
+            if x > 0 {
+                neighbors.push((x - 1, y + i));
+            }
+        }
+        neighbors.retain(|&(x, y)| x < GRID_SIZE && y < GRID_SIZE);
+        neighbors
+    }
+
+    fn fronts_street(&self, x: usize, y: usize) -> bool {
+        Self::footprint_neighbors(x, y)
+            .into_iter()
+            .any(|(x, y)| self.tile(x, y) == Tile::Street)
+    }
+
+    fn footprint_reachable(&self, reachable: &[bool], x: usize, y: usize) -> bool {
+        Self::footprint_neighbors(x, y)
+            .into_iter()
+            .any(|(x, y)| reachable[y * GRID_SIZE + x])
+    }
+
+    fn nearest_street(&self, x: usize, y: usize) -> (usize, usize) {
+        (0..GRID_SIZE * GRID_SIZE)
+            .filter(|&i| self.tiles[i] == Tile::Street)
+            .map(|i| (i % GRID_SIZE, i / GRID_SIZE))
+            .min_by_key(|&(sx, sy)| sx.abs_diff(x) + sy.abs_diff(y))
+            .unwrap()
+    }
+
+    /// Flood fill over everything that isn't a house.
+    fn reachable_from(&self, x: usize, y: usize) -> Vec<bool> {
+        let mut reachable = vec![false; GRID_SIZE * GRID_SIZE];
+        let mut queue = VecDeque::from([(x, y)]);
+        reachable[y * GRID_SIZE + x] = true;
+
+        while let Some((x, y)) = queue.pop_front() {
+            let neighbors = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+            for (nx, ny) in neighbors {
+                if nx >= GRID_SIZE || ny >= GRID_SIZE {
+                    continue;
+                }
+                let i = ny * GRID_SIZE + nx;
+                if !reachable[i] && self.tiles[i] != Tile::House {
+                    reachable[i] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        reachable
+    }
+
+    fn neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = Tile> + '_ {
+        [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ]
+        .into_iter()
+        .filter(|&(x, y)| x < GRID_SIZE && y < GRID_SIZE)
+        .map(|(x, y)| self.tile(x, y))
+    }
+
+    /// Ground and decoration for the tile map. Lawn along the streets becomes sidewalk and
+    /// parks get fenced off from the yards next to them.
+    fn level_tiles(&self, rng: &mut StdRng) -> LevelTiles {
+        let mut tiles = LevelTiles {
+            origin: cell_center(0, 0) - glm::vec2(CELL_SIZE, CELL_SIZE) * 0.5,
+            tile_size: CELL_SIZE,
+            ..Default::default()
+        };
+
+        for y in (0..GRID_SIZE).rev() {
+            let mut ground = String::new();
+            let mut decoration = String::new();
+            for x in 0..GRID_SIZE {
+                let near = |tile| self.neighbors(x, y).any(|other| other == tile);
+                let (base, detail) = match self.tile(x, y) {
+                    Tile::Street => (TileId::Street, None),
+                    Tile::Park => (
+                        TileId::Park,
+                        (near(Tile::Lawn) || near(Tile::House)).then_some(TileId::Fence),
+                    ),
+                    Tile::Lawn | Tile::House if near(Tile::Street) => (TileId::Sidewalk, None),
+                    Tile::House => (TileId::Grass, None),
+                    Tile::Lawn => {
+                        let roll = rng.gen::<f64>();
+                        let detail = if roll < LEAVES_CHANCE {
+                            Some(TileId::Leaves)
+                        } else if roll < LEAVES_CHANCE + FLOWERS_CHANCE {
+                            Some(TileId::Flowers)
+                        } else {
+                            None
+                        };
+                        (TileId::Grass, detail)
+                    }
+                };
+                ground.push(base.to_char());
+                decoration.push(detail.map_or(' ', TileId::to_char));
+            }
+            tiles.ground.push(ground);
+            tiles.decoration.push(decoration);
+        }
+        tiles
+    }
+}
+
+pub fn cell_center(x: usize, y: usize) -> glm::Vec2 {
+    let origin = -(GRID_SIZE as f32) * CELL_SIZE * 0.5;
+    glm::vec2(
+        origin + (x as f32 + 0.5) * CELL_SIZE,
+        origin + (y as f32 + 0.5) * CELL_SIZE,
+    )
+}
+
+fn footprint_center(x: usize, y: usize) -> glm::Vec2 {
+    (cell_center(x, y) + cell_center(x + HOUSE_SIZE - 1, y + HOUSE_SIZE - 1)) * 0.5
+}
+
+/// Middle of a street crossing, from its bottom left cell.
+fn street_center(x: usize, y: usize) -> glm::Vec2 {
+    (cell_center(x, y) + cell_center(x + STREET_WIDTH - 1, y + STREET_WIDTH - 1)) * 0.5
+}
+
+/// Street lines from edge to edge, with the outer ring always a street so nothing is cut off.
+fn street_lines(rng: &mut StdRng) -> Vec<usize> {
+    let last = GRID_SIZE - STREET_WIDTH;
+    let mut lines = vec![0];
+    loop {
+        let next = lines.last().unwrap() + rng.gen_range(BLOCK_SPACING);
+        if next + BLOCK_SPACING.start() > last {
+            break;
+        }
+        lines.push(next);
+    }
+    lines.push(last);
+    lines
+}
+
+pub fn neighborhood_name_render(galaxy: &Galaxy) {
+    let level = galaxy
+        .get_resource::<Level, _>(Level::single_resource())
+        .unwrap();
+    let camera = *galaxy
+        .get_resource::<RendererCamera, _>(RendererCamera::single_resource())
+        .unwrap();
+    draw_text(
+        galaxy,
+        &level.name,
+        camera.position + NAME_POSITION,
+        NAME_SIZE,
+        NAME_COLOR,
+    );
+}
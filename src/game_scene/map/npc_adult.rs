@@ -0,0 +1,328 @@
+use super::*;
+
+fn profile(tuning: &AdultTuning, kind: AdultKind) -> AdultProfile {
+    match kind {
+        AdultKind::Parent => tuning.parent,
+        AdultKind::Police => tuning.police,
+    }
+}
+
+/// How adults of `kind` are tinted, here and in the editor.
+pub fn adult_color(kind: AdultKind) -> glm::Vec4 {
+    match kind {
+        AdultKind::Parent => glm::vec4(1.0, 0.85, 0.75, 1.0),
+        AdultKind::Police => glm::vec4(0.55, 0.65, 1.0, 1.0),
+    }
+}
+
+/// A kid got punched here, for any adult looking that way.
+#[derive(Event, Clone, Copy)]
+pub struct KidPunched {
+    pub position: glm::Vec2,
+}
+
+#[derive(CheapComponent, Clone, Copy, Serialize, Deserialize)]
+pub struct Adult {
+    kind: AdultKind,
+    position: glm::Vec2,
+    /// Which way they're looking, always unit length.
+    facing: glm::Vec2,
+    state: AdultState,
+    /// The level adult this one spawned as, whose patrol it walks.
+    spawn: usize,
+    waypoint: usize,
+    last_caught: Option<GameInstant>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+enum AdultState {
+    /// Walking to the next patrol point, or standing watch without a patrol.
+    Patrolling(Option<(glm::Vec2, NavRoute)>),
+    /// Going to see about a kid calling for help.
+    Answering(glm::Vec2, NavRoute),
+    /// After the player, who was last seen here at this time.
+    Chasing(glm::Vec2, GameInstant),
+}
+
+impl Adult {
+    pub fn as_sprite(&self) -> Sprite {
+        Sprite {
+            texture_index: SPRITE_GRANNY,
+            position: self.position,
+            scale: glm::vec2(0.4, 0.4),
+            color: adult_color(self.kind),
+            flip_x: self.facing.x < 0.0,
+            ..Default::default()
+        }
+    }
+
+    /// Inside the vision cone and not behind a house.
+    fn sees(&self, point: glm::Vec2, nav: &NavGrid, profile: &AdultProfile) -> bool {
+        let offset = point - self.position;
+        let distance = glm::length(&offset);
+        if distance > profile.sight_range {
+            return false;
+        }
+        if distance > f32::EPSILON
+            && glm::dot(&(offset / distance), &self.facing) < profile.sight_half_angle.cos()
+        {
+            return false;
+        }
+        nav.can_see(self.position, point)
+    }
+
+    /// Back to the patrol, picking up at the next point.
+    fn patrol(&mut self, level: &Level, nav: &NavGrid) {
+        let patrol = level
+            .adults
+            .get(self.spawn)
+            .map(|adult| adult.patrol.as_slice())
+            .unwrap_or_default();
+        self.state = if patrol.is_empty() {
+            AdultState::Patrolling(None)
+        } else {
+            self.waypoint %= patrol.len();
+            self.waypoint += 1;
+            let goal = patrol[self.waypoint - 1];
+            AdultState::Patrolling(Some((goal, nav.route(self.position, goal))))
+        };
+    }
+
+    fn walk(&mut self, step: glm::Vec2, bounds: &LevelBounds, turn_rate: f32) {
+        self.position = bounds.clamp(self.position + step);
+        let length = glm::length(&step);
+        if length > f32::EPSILON {
+            let turned = glm::lerp(&self.facing, &(step / length), turn_rate);
+            if glm::length(&turned) > f32::EPSILON {
+                self.facing = glm::normalize(&turned);
+            }
+        }
+    }
+}
+
+pub fn npc_adult_init(galaxy: &Galaxy, adults: &[LevelAdult]) {
+    for (spawn, adult) in adults.iter().enumerate() {
+        galaxy.insert_entity().insert(Adult {
+            kind: adult.kind,
+            position: adult.position,
+            facing: glm::vec2(0.0, -1.0),
+            state: AdultState::Patrolling(None),
+            spawn,
+            waypoint: 0,
+            last_caught: None,
+        });
+    }
+}
+
+pub fn npc_adult_update(galaxy: &Galaxy) {
+    let now = game_now(galaxy);
+    let tuning = tuning(galaxy).adults;
+    let level = galaxy
+        .get_resource::<Level, _>(Level::single_resource())
+        .unwrap();
+    let mut nav = galaxy
+        .get_mut_resource::<NavGrid, _>(NavGrid::single_resource())
+        .unwrap();
+    let index = galaxy
+        .get_resource::<SpatialIndex, _>(SpatialIndex::single_resource())
+        .unwrap();
+    let mut player = galaxy
+        .get_mut_resource::<Player, _>(Player::single_resource())
+        .unwrap();
+    let punches = galaxy
+        .get_events::<KidPunched>()
+        .iter()
+        .map(|punch| punch.position)
+        .collect::<Vec<_>>();
+    let kids = galaxy.query::<&NpcGeneric>().iter().collect::<Vec<_>>();
+    let houses = galaxy
+        .query::<&House>()
+        .iter()
+        .map(|house| house.position)
+        .collect::<Vec<_>>();
+
+    for adult in galaxy.query::<&mut Adult>().iter() {
+        let profile = profile(&tuning, adult.kind);
+        let sees_player = adult.sees(player.position, &nav, &profile);
+
+        let witnessed = punches
+            .iter()
+            .any(|&punch| sees_player || adult.sees(punch, &nav, &profile));
+        if witnessed {
+            adult.state = AdultState::Chasing(player.position, now);
+        } else if adult.kind == AdultKind::Parent
+            && matches!(adult.state, AdultState::Patrolling(_))
+        {
+            let calling = index
+                .kids
+                .query_radius(adult.position, tuning.hearing_radius)
+                .find(|&i| kids[i].is_calling());
+            if let Some(i) = calling {
+                let kid = kids[i].position;
+                adult.state = AdultState::Answering(kid, nav.route(adult.position, kid));
+            }
+        }
+
+        let (target, speed) = match adult.state {
+            AdultState::Patrolling(None) => {
+                adult.patrol(&level, &nav);
+                continue;
+            }
+            AdultState::Patrolling(Some((goal, mut route))) => {
+                if glm::distance(&goal, &adult.position) < tuning.arrive_radius {
+                    adult.patrol(&level, &nav);
+                    continue;
+                }
+                let target = nav.follow(&mut route, adult.position, goal);
+                adult.state = AdultState::Patrolling(Some((goal, route)));
+                (target, profile.walk_speed)
+            }
+            AdultState::Answering(kid, mut route) => {
+                if glm::distance(&kid, &adult.position) < tuning.arrive_radius {
+                    // The kid points out the player if they're still around.
+                    if sees_player {
+                        adult.state = AdultState::Chasing(player.position, now);
+                    } else {
+                        adult.patrol(&level, &nav);
+                    }
+                    continue;
+                }
+                let target = nav.follow(&mut route, adult.position, kid);
+                adult.state = AdultState::Answering(kid, route);
+                (target, profile.walk_speed)
+            }
+            AdultState::Chasing(last_seen, seen_time) => {
+                let (last_seen, seen_time) = if sees_player {
+                    (player.position, now)
+                } else {
+                    (last_seen, seen_time)
+                };
+                let lost = now.duration_since(seen_time) > tuning.lose_time
+                    || (!sees_player
+                        && glm::distance(&last_seen, &adult.position) < tuning.arrive_radius);
+                if lost {
+                    adult.patrol(&level, &nav);
+                    continue;
+                }
+                adult.state = AdultState::Chasing(last_seen, seen_time);
+
+                let ready = adult
+                    .last_caught
+                    .is_none_or(|last| now.duration_since(last) > tuning.catch_cooldown);
+                if ready && glm::distance(&adult.position, &player.position) < tuning.catch_range {
+                    adult.last_caught = Some(now);
+                    let taken = player.score.min(profile.confiscate);
+                    if taken > 0 {
+                        player.score -= taken;
+                        stats_record(galaxy, |stats| stats.candy_lost += taken);
+                        spawn_score_decrement(galaxy);
+                    }
+                    audio_no(galaxy);
+                    adult.patrol(&level, &nav);
+                    continue;
+                }
+
+                // Straight at the player while in sight, around the houses to where they
+                // were last seen otherwise.
+                let target = if sees_player {
+                    last_seen
+                } else {
+                    let mut route = nav.route(adult.position, last_seen);
+                    nav.follow(&mut route, adult.position, last_seen)
+                };
+                (target, profile.chase_speed)
+            }
+        };
+
+        let nearby_houses = index
+            .houses
+            .query_radius(adult.position, AVOID_RANGE)
+            .map(|i| houses[i]);
+        let push = avoid_houses(adult.position, nearby_houses);
+        let step = steer(seek(adult.position, target, speed), push, speed);
+        adult.walk(step, &level.bounds, tuning.turn_rate);
+    }
+}
+
+pub fn npc_adult_render_update(galaxy: &Galaxy) {
+    for adult in galaxy.query::<&Adult>().iter() {
+        galaxy.insert_event(RendererDrawSprite(adult.as_sprite()));
+    }
+}
+
+/// Debug builds can draw what every adult sees, as rays cut short where houses block them.
+#[cfg(debug_assertions)]
+pub mod cone_debug {
+    use super::*;
+
+    pub const CONE_DEBUG_KEY: VirtualKeyCode = VirtualKeyCode::F2;
+    const CONE_RAYS: usize = 9;
+    const RAY_STEP: f32 = 0.05;
+    const RAY_WIDTH: f32 = 0.01;
+    const CONE_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 1.0, 0.6, 0.35);
+    const CONE_CHASE_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 0.3, 0.3, 0.5);
+
+    #[derive(SingleResource, Default)]
+    struct ConeDebug {
+        visible: bool,
+    }
+
+    pub fn cone_debug_init(galaxy: &Galaxy) {
+        galaxy.insert_resource(ConeDebug::single_resource(), ConeDebug::default());
+    }
+
+    pub fn cone_debug_toggle(galaxy: &Galaxy) {
+        let mut debug = galaxy
+            .get_mut_resource::<ConeDebug, _>(ConeDebug::single_resource())
+            .unwrap();
+        debug.visible = !debug.visible;
+    }
+
+    pub fn cone_debug_render_update(galaxy: &Galaxy) {
+        if !galaxy
+            .get_resource::<ConeDebug, _>(ConeDebug::single_resource())
+            .unwrap()
+            .visible
+        {
+            return;
+        }
+        let tuning = tuning(galaxy).adults;
+        let nav = galaxy
+            .get_resource::<NavGrid, _>(NavGrid::single_resource())
+            .unwrap();
+
+        for adult in galaxy.query::<&Adult>().iter() {
+            let profile = profile(&tuning, adult.kind);
+            let color = if matches!(adult.state, AdultState::Chasing(_, _)) {
+                CONE_CHASE_COLOR
+            } else {
+                CONE_COLOR
+            };
+            let heading = adult.facing.y.atan2(adult.facing.x);
+            for ray in 0..CONE_RAYS {
+                let t = ray as f32 / (CONE_RAYS - 1) as f32;
+                let angle = heading + (t * 2.0 - 1.0) * profile.sight_half_angle;
+                let direction = glm::vec2(angle.cos(), angle.sin());
+                let mut length = 0.0;
+                while length < profile.sight_range
+                    && nav.can_see(
+                        adult.position,
+                        adult.position + direction * (length + RAY_STEP),
+                    )
+                {
+                    length += RAY_STEP;
+                }
+                galaxy.insert_event(RendererDrawSprite(Sprite {
+                    texture_index: SPRITE_WHITE,
+                    position: adult.position + direction * length * 0.5,
+                    rotation: angle,
+                    scale: glm::vec2(length, RAY_WIDTH),
+                    color,
+                    blend: SpriteBlend::Additive,
+                    unlit: true,
+                    ..Default::default()
+                }));
+            }
+        }
+    }
+}
@@ -1,122 +1,393 @@
 use super::*;
 
-const SPAWN_COUNT: usize = 12;
-const WALK_SPEED: f32 = 0.01;
-const BEATING_COOLDOWN: Duration = Duration::from_secs(12);
-const HOUSE_IDLE_TIME: Duration = Duration::from_secs(2);
-const PLAYER_PUNCH_RANGE: f32 = 0.6;
-const CRYING_SLIDE_SPEED: f32 = 0.001;
-const CRYING_TIME: Duration = Duration::from_secs(5);
-
-#[derive(CheapComponent, Clone, Copy)]
+const HIT_FLASH_DURATION: Duration = Duration::from_millis(600);
+const HIT_FLASH_INTERVAL_MS: u128 = 80;
+/// Kids heading for a door get in line once it's this close and in sight.
+const QUEUE_JOIN_RADIUS: f32 = 0.8;
+/// Close enough to the door to knock.
+const KNOCK_RADIUS: f32 = 0.1;
+const CALL_TEXT_OFFSET: glm::Vec2 = glm::Vec2::new(0.0, 0.28);
+const CALL_TEXT_SIZE: f32 = 0.05;
+const CALL_TEXT_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 0.9, 0.4, 1.0);
+
+#[derive(CheapComponent, Clone, Copy, Serialize, Deserialize)]
 pub struct NpcGeneric {
-    position: glm::Vec2,
+    pub position: glm::Vec2,
     sprite_texture: usize,
-    last_beaten: Instant,
+    last_beaten: GameInstant,
+    facing_left: bool,
     state: NpcGenericState,
+    mind: NpcMind,
+    /// The level kid this one spawned as, whose patrol it walks.
+    spawn: usize,
+    waypoint: usize,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 enum NpcGenericState {
     Clueless,
-    Walking(glm::Vec2),
-    Idle(Instant),
-    Crying(glm::Vec2, Instant),
+    Walking(NpcGoal, NavRoute),
+    /// Waiting for a turn at a door, since when.
+    Queued(glm::Vec2, GameInstant),
+    /// At the front of the line, knocking.
+    Knocking(glm::Vec2, GameInstant),
+    Idle(GameInstant),
+    Crying(glm::Vec2, GameInstant),
+    /// Running from the player.
+    Fleeing,
+    /// Out to shove the player, since when.
+    Revenge(GameInstant),
+    /// Yelling for a grown up, since when.
+    Calling(GameInstant),
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+enum NpcGoal {
+    Door(glm::Vec2),
+    Waypoint(glm::Vec2),
+}
+
+impl NpcGoal {
+    fn position(&self) -> glm::Vec2 {
+        match *self {
+            NpcGoal::Door(position) | NpcGoal::Waypoint(position) => position,
+        }
+    }
 }
 
 impl NpcGeneric {
-    pub fn as_sprite(&self) -> Sprite {
+    pub fn as_sprite(&self, now: GameInstant) -> Sprite {
+        let since_beaten = now.duration_since(self.last_beaten);
+        let flashing = matches!(self.state, NpcGenericState::Crying(_, _))
+            && since_beaten < HIT_FLASH_DURATION
+            && (since_beaten.as_millis() / HIT_FLASH_INTERVAL_MS) % 2 == 0;
+
         Sprite {
-            visible: 1.0,
             texture_index: match self.state {
                 NpcGenericState::Crying(_, _) => SPRITE_CRYING,
                 _ => self.sprite_texture,
             },
             position: self.position,
             scale: glm::vec2(0.35, 0.35),
-            color: glm::vec3(1.0, 1.0, 1.0),
+            color: if flashing {
+                glm::vec4(1.0, 0.2, 0.2, 1.0)
+            } else if matches!(self.state, NpcGenericState::Revenge(_)) {
+                glm::vec4(1.0, 0.6, 0.6, 1.0)
+            } else {
+                glm::vec4(1.0, 1.0, 1.0, 1.0)
+            },
+            flip_x: self.facing_left,
             ..Default::default()
         }
     }
 
-    pub fn can_be_beaten(&self) -> bool {
-        Instant::now().duration_since(self.last_beaten) > BEATING_COOLDOWN
+    pub fn can_be_beaten(&self, now: GameInstant, cooldown: Duration) -> bool {
+        now.duration_since(self.last_beaten) > cooldown
     }
-}
 
-pub fn npc_generic_init(galaxy: &Galaxy) {
-    let mut rng = rand::thread_rng();
-    for _ in 0..SPAWN_COUNT {
-        let position = glm::vec2(
-            (rng.gen::<f32>() - 0.5) * 0.75 * MAP_BOUND_SIZE,
-            (rng.gen::<f32>() - 0.5) * 0.75 * MAP_BOUND_SIZE,
-        );
+    pub fn is_calling(&self) -> bool {
+        matches!(self.state, NpcGenericState::Calling(_))
+    }
+
+    /// Running, fighting or calling for help once the mind says so, and back to errands once
+    /// it has settled down. `gang` is how many angry kids are around, counting this one.
+    fn react(
+        &mut self,
+        now: GameInstant,
+        player: glm::Vec2,
+        gang: usize,
+        tuning: &NpcTuning,
+        mind: &MindTuning,
+        rng: &mut impl Rng,
+    ) {
+        let player_distance = glm::distance(&self.position, &player);
+        match self.state {
+            NpcGenericState::Crying(_, _) => {}
+            NpcGenericState::Calling(since) => {
+                if now.duration_since(since) > tuning.call_time {
+                    self.mind.reassured(mind);
+                    self.state = NpcGenericState::Clueless;
+                }
+            }
+            NpcGenericState::Fleeing => {
+                let safe = player_distance > tuning.flee_radius * tuning.flee_safe_scale;
+                if safe && self.mind.wants_parent(mind) {
+                    self.state = NpcGenericState::Calling(now);
+                } else if safe || !self.mind.wants_to_flee(mind) {
+                    self.state = NpcGenericState::Clueless;
+                }
+            }
+            NpcGenericState::Revenge(since) => {
+                if now.duration_since(since) > tuning.revenge_time || !self.mind.wants_revenge(mind)
+                {
+                    self.state = NpcGenericState::Clueless;
+                }
+            }
+            _ => {
+                if self.mind.wants_to_flee(mind) && player_distance < tuning.flee_radius {
+                    self.state = NpcGenericState::Fleeing;
+                } else if self.mind.wants_revenge(mind)
+                    && gang >= tuning.gang_size
+                    && rng.gen_bool(tuning.gang_chance)
+                {
+                    self.state = NpcGenericState::Revenge(now);
+                } else if self.mind.wants_parent(mind) {
+                    self.state = NpcGenericState::Calling(now);
+                }
+            }
+        }
+    }
+}
 
+pub fn npc_generic_init(galaxy: &Galaxy, kids: &[LevelKid], count: usize) {
+    let mut rng = game_rng(galaxy, RngStream::Npc);
+    for spawn in (0..kids.len()).cycle().take(count) {
         let sprite_texture = SPRITE_KID1 + rng.gen_range(0..2);
+        let bravery = rng.gen::<f32>();
 
         galaxy.insert_entity().insert(NpcGeneric {
-            position,
+            position: kids[spawn].position,
             sprite_texture,
-            last_beaten: Instant::now(),
+            last_beaten: game_now(galaxy),
+            facing_left: false,
             state: NpcGenericState::Clueless,
+            mind: NpcMind::new(bravery),
+            spawn,
+            waypoint: 0,
         });
     }
 }
 
 pub fn npc_generic_update(galaxy: &Galaxy) {
-    let mut rng = rand::thread_rng();
+    let now = game_now(galaxy);
+    let mind = tuning(galaxy).mind;
+    let tuning = tuning(galaxy).npc;
+    let mut rng = game_rng(galaxy, RngStream::Npc);
     let houses = galaxy.query::<&House>().iter().collect::<Vec<_>>();
+    let index = galaxy
+        .get_resource::<SpatialIndex, _>(SpatialIndex::single_resource())
+        .unwrap();
+    let level = galaxy
+        .get_resource::<Level, _>(Level::single_resource())
+        .unwrap();
+    let mut nav = galaxy
+        .get_mut_resource::<NavGrid, _>(NavGrid::single_resource())
+        .unwrap();
+    let mut player = galaxy
+        .get_mut_resource::<Player, _>(Player::single_resource())
+        .unwrap();
+    // Everyone as of the start of this update, to steer around and hear from.
+    let kids = galaxy
+        .query::<&NpcGeneric>()
+        .iter()
+        .copied()
+        .collect::<Vec<_>>();
+
+    for (i, npc) in galaxy.query::<&mut NpcGeneric>().iter().enumerate() {
+        // Everyone nearby heard this kid's news last tick, from the copy in `kids`.
+        npc.mind.told();
+        for j in index.kids.query_radius(npc.position, mind.warn_radius) {
+            if j != i && kids[j].mind.has_news() {
+                npc.mind.hear(&kids[j].mind, &mind);
+            }
+        }
+        npc.mind
+            .update(now, npc.position, player.position, &tuning, &mind);
 
-    for npc in galaxy.query::<&mut NpcGeneric>().iter() {
+        let gang = if npc.mind.wants_revenge(&mind) {
+            index
+                .kids
+                .query_radius(npc.position, tuning.gang_radius)
+                .filter(|&j| kids[j].mind.wants_revenge(&mind))
+                .count()
+        } else {
+            0
+        };
+        npc.react(now, player.position, gang, &tuning, &mind, &mut rng);
+
+        let mut desired = glm::Vec2::zeros();
+        let mut speed = tuning.walk_speed;
         match npc.state {
             NpcGenericState::Clueless => {
-                let goto_house_idx = rng.gen_range(0..houses.len());
-                npc.state = NpcGenericState::Walking(houses[goto_house_idx].position)
+                let patrol = level
+                    .kids
+                    .get(npc.spawn)
+                    .map(|kid| kid.patrol.as_slice())
+                    .unwrap_or_default();
+                let goal = if !patrol.is_empty() {
+                    npc.waypoint %= patrol.len();
+                    npc.waypoint += 1;
+                    NpcGoal::Waypoint(patrol[npc.waypoint - 1])
+                } else if !houses.is_empty() {
+                    NpcGoal::Door(houses[rng.gen_range(0..houses.len())].door())
+                } else {
+                    continue;
+                };
+                npc.state =
+                    NpcGenericState::Walking(goal, nav.route(npc.position, goal.position()));
             }
-            NpcGenericState::Walking(goto_location) => {
-                let dist = goto_location - npc.position;
-                let dir = glm::normalize(&dist) * WALK_SPEED;
-                npc.position += dir;
+            NpcGenericState::Walking(goal, mut route) => {
+                let distance = glm::distance(&goal.position(), &npc.position);
+                match goal {
+                    NpcGoal::Door(door)
+                        if distance < QUEUE_JOIN_RADIUS
+                            && nav.line_of_sight(npc.position, door) =>
+                    {
+                        npc.state = NpcGenericState::Queued(door, now);
+                        continue;
+                    }
+                    NpcGoal::Waypoint(_) if distance < 0.3 => {
+                        npc.state = NpcGenericState::Idle(now);
+                        continue;
+                    }
+                    _ => {}
+                }
 
-                if glm::length(&dist) < 0.3 {
-                    npc.state = NpcGenericState::Idle(Instant::now())
+                let target = nav.follow(&mut route, npc.position, goal.position());
+                desired = seek(npc.position, target, tuning.walk_speed);
+                npc.state = NpcGenericState::Walking(goal, route);
+            }
+            NpcGenericState::Queued(door, since) => {
+                let place = queue_place(&index.kids, &kids, i, door, since);
+                if place >= QUEUE_LENGTH {
+                    npc.state = NpcGenericState::Clueless;
+                    continue;
+                }
+                if place == 0 && glm::distance(&door, &npc.position) < KNOCK_RADIUS {
+                    npc.state = NpcGenericState::Knocking(door, now);
+                }
+                desired = arrive(npc.position, queue_spot(door, place), tuning.walk_speed);
+            }
+            NpcGenericState::Knocking(door, knock_time) => {
+                if now.duration_since(knock_time) > tuning.house_idle_time {
+                    npc.state = NpcGenericState::Clueless;
                 }
+                desired = arrive(npc.position, door, tuning.walk_speed);
             }
             NpcGenericState::Idle(idle_time) => {
-                if Instant::now().duration_since(idle_time) > HOUSE_IDLE_TIME {
+                if now.duration_since(idle_time) > tuning.house_idle_time {
                     npc.state = NpcGenericState::Clueless
                 }
             }
             NpcGenericState::Crying(velocity, crying_time) => {
-                npc.position += velocity;
-                if Instant::now().duration_since(crying_time) > CRYING_TIME {
+                npc.position = level.bounds.clamp(npc.position + velocity);
+                if now.duration_since(crying_time) > tuning.crying_time {
                     npc.state = NpcGenericState::Clueless
                 }
+                continue;
             }
+            NpcGenericState::Fleeing => {
+                speed *= tuning.flee_speed_scale;
+                let away = npc.position * 2.0 - player.position;
+                desired = seek(npc.position, away, speed);
+            }
+            NpcGenericState::Revenge(_) => {
+                speed *= tuning.revenge_speed_scale;
+                if glm::distance(&npc.position, &player.position) < tuning.shove_range {
+                    let push = player.position - npc.position;
+                    if glm::length(&push) > f32::EPSILON {
+                        player.shove(glm::normalize(&push) * tuning.shove_speed);
+                    }
+                    npc.mind.vented(&mind);
+                    npc.state = NpcGenericState::Clueless;
+                }
+                desired = seek(npc.position, player.position, speed);
+            }
+            NpcGenericState::Calling(_) => {}
         };
+
+        // Kids stacked on the same spot each get pushed their own way.
+        let side = glm::vec2((i as f32).cos(), (i as f32).sin());
+        let others = index
+            .kids
+            .query_radius(npc.position, SEPARATION_RADIUS)
+            .filter(|&j| j != i && !matches!(kids[j].state, NpcGenericState::Crying(_, _)))
+            .map(|j| kids[j].position);
+        let nearby_houses = index
+            .houses
+            .query_radius(npc.position, AVOID_RANGE)
+            .map(|j| houses[j].position);
+        let push = separation(npc.position, others, player.position, side)
+            + avoid_houses(npc.position, nearby_houses);
+        let step = steer(desired, push, speed);
+        npc.position = level.bounds.clamp(npc.position + step);
+        // Only turn around for real moves, not jostling.
+        if step.x.abs() > tuning.walk_speed * 0.25 {
+            npc.facing_left = step.x < 0.0;
+        }
     }
 }
 
+/// How many kids are ahead of kid `i` in line at `door`.
+fn queue_place(
+    grid: &SpatialGrid,
+    kids: &[NpcGeneric],
+    i: usize,
+    door: glm::Vec2,
+    since: GameInstant,
+) -> usize {
+    grid.query_radius(door, QUEUE_JOIN_RADIUS + SEPARATION_RADIUS)
+        .filter(|&j| match kids[j].state {
+            NpcGenericState::Knocking(at, _) => j != i && at == door,
+            NpcGenericState::Queued(at, queued) => at == door && (queued, j) < (since, i),
+            _ => false,
+        })
+        .count()
+}
+
 pub fn npc_generic_punch_update(galaxy: &Galaxy) {
+    let now = game_now(galaxy);
     let mut player = galaxy
         .get_mut_resource::<Player, _>(Player::single_resource())
         .unwrap();
 
-    for ev in galaxy.get_events::<WindowEvent>() {
-        if let WindowEventData::KeyboardInput { input, .. } = ev.0 {
-            if input.virtual_keycode == Some(VirtualKeyCode::F)
-                && input.state == ElementState::Released
+    let keys = key_bindings(galaxy);
+    let cooldown = rules(galaxy).beating_cooldown;
+    let notoriety = tuning(galaxy).notoriety.punch;
+    let mind = tuning(galaxy).mind;
+    let tuning = tuning(galaxy).npc;
+    for input in keyboard_inputs(galaxy) {
+        if input.virtual_keycode == Some(keys.punch) && input.state == ElementState::Released {
+            player.set_punch_frame(now);
+            audio_punch(galaxy);
+            let index = galaxy
+                .get_resource::<SpatialIndex, _>(SpatialIndex::single_resource())
+                .unwrap();
+
+            let mut npcs = galaxy.query::<&mut NpcGeneric>().iter().collect::<Vec<_>>();
+            let mut punched = vec![];
+            for i in index
+                .kids
+                .query_radius(player.position, tuning.player_punch_range)
             {
-                player.set_punch_frame();
-                audio_punch(galaxy);
-                for npc in galaxy.query::<&mut NpcGeneric>().iter() {
-                    if glm::distance(&npc.position, &player.position) < PLAYER_PUNCH_RANGE
-                        && npc.can_be_beaten()
-                    {
-                        let velocity = (player.position - npc.position) * CRYING_SLIDE_SPEED;
-                        blow_candy(galaxy, npc.position);
-                        npc.last_beaten = Instant::now();
-                        npc.state = NpcGenericState::Crying(velocity, npc.last_beaten);
+                let npc = &mut *npcs[i];
+                if npc.can_be_beaten(now, cooldown) {
+                    let velocity = (player.position - npc.position) * tuning.crying_slide_speed;
+                    blow_candy(galaxy, npc.position);
+                    spawn_particle_emitter(
+                        galaxy,
+                        GameState::Map,
+                        npc.position,
+                        ParticleEmitterConfig::punch_stars(),
+                    );
+                    stats_record(galaxy, |stats| stats.kids_punched += 1);
+                    notoriety_raise(galaxy, notoriety);
+                    galaxy.insert_event(KidPunched {
+                        position: npc.position,
+                    });
+                    npc.last_beaten = now;
+                    npc.state = NpcGenericState::Crying(velocity, npc.last_beaten);
+                    npc.mind.punched(player.position, now, &mind);
+                    punched.push(i);
+                }
+            }
+            for &victim in &punched {
+                for j in index
+                    .kids
+                    .query_radius(npcs[victim].position, mind.witness_radius)
+                {
+                    if !punched.contains(&j) {
+                        npcs[j].mind.witnessed(player.position, now, &mind);
                     }
                 }
             }
@@ -125,7 +396,17 @@ pub fn npc_generic_punch_update(galaxy: &Galaxy) {
 }
 
 pub fn npc_generic_render_update(galaxy: &Galaxy) {
+    let now = game_now(galaxy);
     for npc in galaxy.query::<&NpcGeneric>().iter() {
-        galaxy.insert_event(RendererDrawSprite(npc.as_sprite()));
+        galaxy.insert_event(RendererDrawSprite(npc.as_sprite(now)));
+        if matches!(npc.state, NpcGenericState::Calling(_)) {
+            draw_text(
+                galaxy,
+                "HELP!",
+                npc.position + CALL_TEXT_OFFSET,
+                CALL_TEXT_SIZE,
+                CALL_TEXT_COLOR,
+            );
+        }
     }
 }
@@ -0,0 +1,117 @@
+//! What kids make of the player. Fear and anger build up from punches and from what other
+//! kids say, fade over time, and decide whether a kid runs, fights back or calls for help.
+
+use super::*;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct NpcMind {
+    fear: f32,
+    anger: f32,
+    /// From 0.0 to 1.0. Brave kids get angry where others get scared.
+    bravery: f32,
+    /// Where the player was last seen up to no good, and when.
+    memory: Option<(glm::Vec2, GameInstant)>,
+    /// Something happened that the kids around should hear about.
+    news: bool,
+}
+
+impl NpcMind {
+    pub fn new(bravery: f32) -> Self {
+        NpcMind {
+            fear: 0.0,
+            anger: 0.0,
+            bravery,
+            memory: None,
+            news: false,
+        }
+    }
+
+    fn feel(&mut self, fear: f32, anger: f32) {
+        self.fear = (self.fear + fear * (1.0 - self.bravery)).min(1.0);
+        self.anger = (self.anger + anger * self.bravery).min(1.0);
+    }
+
+    pub fn punched(&mut self, player: glm::Vec2, now: GameInstant, tuning: &MindTuning) {
+        self.feel(tuning.punch_fear, tuning.punch_anger);
+        self.memory = Some((player, now));
+        self.news = true;
+    }
+
+    pub fn witnessed(&mut self, player: glm::Vec2, now: GameInstant, tuning: &MindTuning) {
+        self.feel(tuning.witness_fear, tuning.witness_anger);
+        self.memory = Some((player, now));
+        self.news = true;
+    }
+
+    /// Take in what `other` has to say, unless it's old news.
+    pub fn hear(&mut self, other: &NpcMind, tuning: &MindTuning) {
+        let Some((_, seen)) = other.memory else {
+            return;
+        };
+        if self.memory.is_some_and(|(_, known)| known >= seen) {
+            return;
+        }
+        let (fear, anger) = (
+            other.fear * tuning.warn_share,
+            other.anger * tuning.warn_share,
+        );
+        self.fear = self.fear.max(fear);
+        self.anger = self.anger.max(anger);
+        self.memory = other.memory;
+        self.news = fear.max(anger) > tuning.warn_min_feeling;
+    }
+
+    pub fn has_news(&self) -> bool {
+        self.news
+    }
+
+    /// Done telling everyone around, see `hear`.
+    pub fn told(&mut self) {
+        self.news = false;
+    }
+
+    /// Let feelings fade, forget the player after a while, and get worked up again while
+    /// a remembered player is hanging around.
+    pub fn update(
+        &mut self,
+        now: GameInstant,
+        position: glm::Vec2,
+        player: glm::Vec2,
+        npc: &NpcTuning,
+        tuning: &MindTuning,
+    ) {
+        self.fear *= tuning.feeling_decay;
+        self.anger *= tuning.feeling_decay;
+        let Some((_, seen)) = self.memory else {
+            return;
+        };
+        if now.duration_since(seen) > npc.memory_time {
+            self.memory = None;
+        } else if glm::distance(&position, &player) < npc.flee_radius {
+            self.feel(tuning.recognize_feeling, tuning.recognize_feeling);
+            self.memory = Some((player, now));
+        }
+    }
+
+    pub fn wants_to_flee(&self, tuning: &MindTuning) -> bool {
+        self.fear > tuning.flee_fear && self.fear >= self.anger
+    }
+
+    pub fn wants_revenge(&self, tuning: &MindTuning) -> bool {
+        self.anger > tuning.revenge_anger && self.anger > self.fear
+    }
+
+    pub fn wants_parent(&self, tuning: &MindTuning) -> bool {
+        self.fear > tuning.call_fear
+    }
+
+    /// A grown up is on the way.
+    pub fn reassured(&mut self, tuning: &MindTuning) {
+        self.fear *= tuning.reassured_fear;
+    }
+
+    /// Got a shove in, that'll do for now.
+    pub fn vented(&mut self, tuning: &MindTuning) {
+        self.anger *= tuning.vented_anger;
+    }
+}
@@ -0,0 +1,123 @@
+use super::*;
+
+/// Close enough to a door to move on to the next house.
+const ROUND_RADIUS: f32 = 0.3;
+const FLASHLIGHT_REACH: f32 = 0.4;
+
+/// A grown up in a hi-vis vest, out walking from door to door once the player has been
+/// causing enough trouble. How many there are follows `Notoriety`, so they aren't saved.
+#[derive(CheapComponent, Clone, Copy)]
+pub struct NeighborhoodWatch {
+    position: glm::Vec2,
+    goal: glm::Vec2,
+    route: NavRoute,
+    facing_left: bool,
+    last_confiscated: Option<GameInstant>,
+}
+
+impl NeighborhoodWatch {
+    pub fn as_sprite(&self) -> Sprite {
+        Sprite {
+            texture_index: SPRITE_GRANNY,
+            position: self.position,
+            scale: glm::vec2(0.4, 0.4),
+            color: glm::vec4(0.85, 1.0, 0.5, 1.0),
+            flip_x: self.facing_left,
+            ..Default::default()
+        }
+    }
+
+    pub fn as_light(&self) -> Light {
+        let facing = if self.facing_left { -1.0 } else { 1.0 };
+        Light {
+            position: self.position + glm::vec2(facing * FLASHLIGHT_REACH, 0.0),
+            radius: 0.6,
+            color: glm::vec3(1.0, 0.95, 0.8),
+            intensity: 0.8,
+        }
+    }
+}
+
+fn next_door(houses: &[&House], rng: &mut impl Rng) -> Option<glm::Vec2> {
+    (!houses.is_empty()).then(|| houses[rng.gen_range(0..houses.len())].door())
+}
+
+/// Bring watch adults out or send them home to match the notoriety, then walk the rounds.
+pub fn npc_watch_update(galaxy: &Galaxy) {
+    let now = game_now(galaxy);
+    let tuning = tuning(galaxy).notoriety;
+    let mut rng = game_rng(galaxy, RngStream::Npc);
+    let houses = galaxy.query::<&House>().iter().collect::<Vec<_>>();
+    let level = galaxy
+        .get_resource::<Level, _>(Level::single_resource())
+        .unwrap();
+    let mut nav = galaxy
+        .get_mut_resource::<NavGrid, _>(NavGrid::single_resource())
+        .unwrap();
+    let mut player = galaxy
+        .get_mut_resource::<Player, _>(Player::single_resource())
+        .unwrap();
+
+    let watchers = galaxy
+        .query::<&NeighborhoodWatch>()
+        .eiter()
+        .collect::<Vec<_>>();
+    let wanted = notoriety(galaxy).watchers(&tuning);
+    if watchers.len() > wanted {
+        for &(e, _) in &watchers[wanted..] {
+            galaxy.remove_entity(e);
+        }
+    } else if watchers.len() < wanted {
+        // Out of whichever kid spawn is furthest from the player, so they walk into view.
+        let spawn = level.kids.iter().map(|kid| kid.position).max_by(|a, b| {
+            glm::distance(a, &player.position).total_cmp(&glm::distance(b, &player.position))
+        });
+        if let (Some(position), Some(goal)) = (spawn, next_door(&houses, &mut rng)) {
+            galaxy.insert_entity().insert(NeighborhoodWatch {
+                position,
+                goal,
+                route: nav.route(position, goal),
+                facing_left: false,
+                last_confiscated: None,
+            });
+        }
+    }
+
+    for watch in galaxy.query::<&mut NeighborhoodWatch>().iter() {
+        if glm::distance(&watch.goal, &watch.position) < ROUND_RADIUS {
+            if let Some(goal) = next_door(&houses, &mut rng) {
+                watch.goal = goal;
+                watch.route = nav.route(watch.position, goal);
+            }
+        }
+
+        let target = nav.follow(&mut watch.route, watch.position, watch.goal);
+        let step = seek(watch.position, target, tuning.watch_speed);
+        watch.position = level.bounds.clamp(watch.position + step);
+        if step.x.abs() > tuning.watch_speed * 0.25 {
+            watch.facing_left = step.x < 0.0;
+        }
+
+        let ready = watch
+            .last_confiscated
+            .is_none_or(|last| now.duration_since(last) > tuning.confiscate_cooldown);
+        if ready && glm::distance(&watch.position, &player.position) < tuning.watch_contact_range {
+            watch.last_confiscated = Some(now);
+            let lost = player.score.min(tuning.confiscate_amount);
+            if lost > 0 {
+                player.score -= lost;
+                stats_record(galaxy, |stats| stats.candy_lost += lost);
+                spawn_score_decrement(galaxy);
+                audio_no(galaxy);
+                notoriety_lower(galaxy, tuning.confiscate);
+            }
+        }
+    }
+}
+
+pub fn npc_watch_render_update(galaxy: &Galaxy) {
+    for watch in galaxy.query::<&NeighborhoodWatch>().iter() {
+        galaxy.insert_event(RendererDrawSprite(watch.as_sprite()));
+        galaxy.insert_event(RendererDrawLight(watch.as_light()));
+    }
+}
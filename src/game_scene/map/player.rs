@@ -1,42 +1,74 @@
 use super::*;
 
-const VELOCITY_INC: f32 = 0.0006;
-const FRICTION_FACTOR: f32 = 0.001;
-const MAX_COMPONENT_SPEED: f32 = 0.02;
 const PUNCH_FRAME_DURATION: Duration = Duration::from_millis(400);
+const FLASHLIGHT_REACH: f32 = 0.45;
 
-#[derive(SingleResource, Default, Clone, Copy)]
+#[derive(SingleResource, Default, Clone, Copy, Serialize, Deserialize)]
 pub struct Player {
     pub score: usize,
     velocity: glm::Vec2,
     pub position: glm::Vec2,
+    facing: glm::Vec2,
     state: PlayerState,
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone, Copy, Serialize, Deserialize)]
 enum PlayerState {
     #[default]
     Normal,
-    Punching(Instant),
+    Punching(GameInstant),
 }
 
 impl Player {
     pub fn as_sprite(&self) -> Sprite {
         Sprite {
-            visible: 1.0,
             texture_index: match self.state {
                 PlayerState::Normal => SPRITE_OURKID,
                 PlayerState::Punching(_) => SPRITE_KIDPUNCH,
             },
             position: self.position,
             scale: glm::vec2(0.35, 0.35),
-            color: glm::vec3(1.0, 1.0, 1.0),
+            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
+            flip_x: self.facing.x < 0.0,
             ..Default::default()
         }
     }
 
-    pub fn set_punch_frame(&mut self) {
-        self.state = PlayerState::Punching(Instant::now())
+    pub fn as_lights(&self) -> [Light; 2] {
+        [
+            Light {
+                position: self.position,
+                radius: 0.5,
+                color: glm::vec3(0.8, 0.8, 1.0),
+                intensity: 0.5,
+            },
+            Light {
+                position: self.position + self.facing * FLASHLIGHT_REACH,
+                radius: 0.7,
+                color: glm::vec3(1.0, 0.95, 0.8),
+                intensity: 1.0,
+            },
+        ]
+    }
+
+    pub fn set_punch_frame(&mut self, now: GameInstant) {
+        self.state = PlayerState::Punching(now)
+    }
+
+    pub fn shove(&mut self, push: glm::Vec2) {
+        self.velocity += push;
+    }
+
+    /// Stop at the edge of the level rather than sliding along outside it.
+    pub fn keep_within(&mut self, bounds: &LevelBounds) {
+        let clamped = bounds.clamp(self.position);
+        if clamped.x != self.position.x {
+            self.velocity.x = 0.0;
+        }
+        if clamped.y != self.position.y {
+            self.velocity.y = 0.0;
+        }
+        self.position = clamped;
     }
 }
 
@@ -44,64 +76,73 @@ pub fn player_init(galaxy: &Galaxy) {
     galaxy.insert_resource(Player::single_resource(), Player::default());
 }
 
+/// Start over with a fresh player standing at `position`.
+pub fn player_spawn(galaxy: &Galaxy, position: glm::Vec2) {
+    **galaxy
+        .get_mut_resource::<Player, _>(Player::single_resource())
+        .unwrap() = Player {
+        position,
+        ..Default::default()
+    };
+}
+
 pub fn player_movement_update(galaxy: &Galaxy) {
     let mut player = galaxy
         .get_mut_resource::<Player, _>(Player::single_resource())
         .unwrap();
 
+    let rules = rules(galaxy);
     let mut movement_key_pressed = false;
 
-    for ev in galaxy.get_events::<WindowEvent>() {
-        if let WindowEventData::KeyboardInput { input, .. } = ev.0 {
-            if let Some(input) = input.virtual_keycode {
-                match input {
-                    VirtualKeyCode::W => {
-                        player.velocity.y += VELOCITY_INC;
-                        movement_key_pressed = true;
-                    }
-                    VirtualKeyCode::A => {
-                        player.velocity.x -= VELOCITY_INC;
-                        movement_key_pressed = true;
-                    }
-                    VirtualKeyCode::S => {
-                        player.velocity.y -= VELOCITY_INC;
-                        movement_key_pressed = true;
-                    }
-                    VirtualKeyCode::D => {
-                        player.velocity.x += VELOCITY_INC;
-                        movement_key_pressed = true;
-                    }
-                    _ => (),
-                }
-            }
+    let keys = key_bindings(galaxy);
+    for input in keyboard_inputs(galaxy) {
+        let Some(key) = input.virtual_keycode else {
+            continue;
+        };
+        if key == keys.up {
+            player.velocity.y += rules.player_acceleration;
+            movement_key_pressed = true;
+        } else if key == keys.left {
+            player.velocity.x -= rules.player_acceleration;
+            movement_key_pressed = true;
+        } else if key == keys.down {
+            player.velocity.y -= rules.player_acceleration;
+            movement_key_pressed = true;
+        } else if key == keys.right {
+            player.velocity.x += rules.player_acceleration;
+            movement_key_pressed = true;
         }
     }
 
     player.velocity.x = player
         .velocity
         .x
-        .clamp(-MAX_COMPONENT_SPEED, MAX_COMPONENT_SPEED);
+        .clamp(-rules.player_max_speed, rules.player_max_speed);
     player.velocity.y = player
         .velocity
         .y
-        .clamp(-MAX_COMPONENT_SPEED, MAX_COMPONENT_SPEED);
+        .clamp(-rules.player_max_speed, rules.player_max_speed);
 
     let friction_factor = if player.velocity.x.abs() < 0.01 || !movement_key_pressed {
         10.0
     } else {
         1.0
     };
-    player.velocity.x -= player.velocity.x * FRICTION_FACTOR * friction_factor;
+    player.velocity.x -= player.velocity.x * rules.player_friction * friction_factor;
 
     let friction_factor = if player.velocity.y.abs() < 0.01 || !movement_key_pressed {
         10.0
     } else {
         1.0
     };
-    player.velocity.y -= player.velocity.y * FRICTION_FACTOR * friction_factor;
+    player.velocity.y -= player.velocity.y * rules.player_friction * friction_factor;
 
     player.position.x += player.velocity.x;
     player.position.y += player.velocity.y;
+
+    if glm::length(&player.velocity) > 0.001 {
+        player.facing = glm::normalize(&player.velocity);
+    }
 }
 
 pub fn player_render_update(galaxy: &Galaxy) {
@@ -110,12 +151,15 @@ pub fn player_render_update(galaxy: &Galaxy) {
         .unwrap();
 
     if let PlayerState::Punching(punch_time) = player.state {
-        if Instant::now().duration_since(punch_time) > PUNCH_FRAME_DURATION {
+        if game_now(galaxy).duration_since(punch_time) > PUNCH_FRAME_DURATION {
             player.state = PlayerState::Normal;
         }
     }
 
     galaxy.insert_event(RendererDrawSprite(player.as_sprite()));
+    for light in player.as_lights() {
+        galaxy.insert_event(RendererDrawLight(light));
+    }
 
     let mut camera = galaxy
         .get_mut_resource::<RendererCamera, _>(RendererCamera::single_resource())
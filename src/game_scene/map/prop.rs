@@ -0,0 +1,52 @@
+use super::*;
+
+const STREET_LIGHT_HEIGHT: f32 = 0.35;
+
+#[derive(CheapComponent, Clone, Copy)]
+pub struct StreetLight {
+    position: glm::Vec2,
+}
+
+impl StreetLight {
+    pub fn as_sprite(&self) -> Sprite {
+        Sprite {
+            texture_index: SPRITE_WHITE,
+            position: self.position + glm::vec2(0.0, STREET_LIGHT_HEIGHT * 0.5),
+            scale: glm::vec2(0.04, STREET_LIGHT_HEIGHT),
+            color: glm::vec4(0.25, 0.25, 0.28, 1.0),
+            ..Default::default()
+        }
+    }
+
+    pub fn as_light(&self) -> Light {
+        Light {
+            position: self.position + glm::vec2(0.0, STREET_LIGHT_HEIGHT),
+            radius: 1.2,
+            color: glm::vec3(0.85, 0.9, 1.0),
+            intensity: 0.7,
+        }
+    }
+}
+
+/// Decoration has no state worth saving, so it always comes straight from the level.
+pub fn prop_init(galaxy: &Galaxy, props: &[LevelProp]) {
+    let mut rng = game_rng(galaxy, RngStream::Map);
+    for prop in props {
+        match prop.kind {
+            PropKind::JackOLantern => jack_o_lantern_spawn(galaxy, &mut rng, prop.position),
+            PropKind::StreetLight => {
+                galaxy.insert_entity().insert(StreetLight {
+                    position: prop.position,
+                });
+            }
+        }
+    }
+}
+
+pub fn prop_render_update(galaxy: &Galaxy) {
+    jack_o_lantern_render_update(galaxy);
+    for light in galaxy.query::<&StreetLight>().iter() {
+        galaxy.insert_event(RendererDrawSprite(light.as_sprite()));
+        galaxy.insert_event(RendererDrawLight(light.as_light()));
+    }
+}
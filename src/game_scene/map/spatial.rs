@@ -0,0 +1,200 @@
+use super::*;
+use std::collections::HashMap;
+
+const HOUSE_CELL_SIZE: f32 = 1.0;
+const KID_CELL_SIZE: f32 = 0.5;
+const CANDY_CELL_SIZE: f32 = 0.25;
+
+type Cell = (i32, i32);
+
+/// Positions bucketed into square cells, so finding what's near a point only looks at the
+/// cells around it. Entries are indices into whatever list the positions came from.
+#[derive(Default)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    positions: Vec<glm::Vec2>,
+    cells: HashMap<Cell, Vec<usize>>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        SpatialGrid {
+            cell_size,
+            ..Default::default()
+        }
+    }
+
+    /// Replace everything with `positions`. Cells are emptied rather than dropped, so a
+    /// grid rebuilt every tick stops allocating once it has seen the whole map.
+    pub fn rebuild(&mut self, positions: impl Iterator<Item = glm::Vec2>) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        self.positions.clear();
+        for (i, position) in positions.enumerate() {
+            self.cells
+                .entry(self.cell_of(position))
+                .or_default()
+                .push(i);
+            self.positions.push(position);
+        }
+    }
+
+    fn cell_of(&self, position: glm::Vec2) -> Cell {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Everything inside the box from `min` to `max`.
+    pub fn query_aabb(&self, min: glm::Vec2, max: glm::Vec2) -> impl Iterator<Item = usize> + '_ {
+        let (x0, y0) = self.cell_of(min);
+        let (x1, y1) = self.cell_of(max);
+        (y0..=y1)
+            .flat_map(move |y| (x0..=x1).map(move |x| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |&i| {
+                let position = self.positions[i];
+                position.x >= min.x
+                    && position.x <= max.x
+                    && position.y >= min.y
+                    && position.y <= max.y
+            })
+    }
+
+    /// Everything closer than `radius` to `center`.
+    pub fn query_radius(&self, center: glm::Vec2, radius: f32) -> impl Iterator<Item = usize> + '_ {
+        let reach = glm::vec2(radius, radius);
+        self.query_aabb(center - reach, center + reach)
+            .filter(move |&i| glm::distance(&self.positions[i], &center) < radius)
+    }
+}
+
+/// Where everything on the map was at the start of this tick. Indices follow the order the
+/// matching query iterates in, which holds for the whole tick since entities added or
+/// removed only show up in queries on the next one.
+#[derive(SingleResource)]
+pub struct SpatialIndex {
+    pub houses: SpatialGrid,
+    pub kids: SpatialGrid,
+    pub candy: SpatialGrid,
+}
+
+pub fn spatial_index_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(
+        SpatialIndex::single_resource(),
+        SpatialIndex {
+            houses: SpatialGrid::new(HOUSE_CELL_SIZE),
+            kids: SpatialGrid::new(KID_CELL_SIZE),
+            candy: SpatialGrid::new(CANDY_CELL_SIZE),
+        },
+    );
+}
+
+pub fn spatial_index_update(galaxy: &Galaxy) {
+    let mut index = galaxy
+        .get_mut_resource::<SpatialIndex, _>(SpatialIndex::single_resource())
+        .unwrap();
+    index
+        .houses
+        .rebuild(galaxy.query::<&House>().iter().map(|house| house.position));
+    index
+        .kids
+        .rebuild(galaxy.query::<&NpcGeneric>().iter().map(|npc| npc.position));
+    index
+        .candy
+        .rebuild(galaxy.query::<&Candy>().iter().map(|candy| candy.position));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    fn sorted(indices: impl Iterator<Item = usize>) -> Vec<usize> {
+        let mut indices = indices.collect::<Vec<_>>();
+        indices.sort();
+        indices
+    }
+
+    #[test]
+    fn aabb_includes_its_edges() {
+        let mut grid = SpatialGrid::new(0.5);
+        grid.rebuild(
+            [
+                glm::vec2(0.0, 0.0),
+                glm::vec2(1.0, 1.0),
+                glm::vec2(1.0001, 0.5),
+                glm::vec2(-0.0001, 0.5),
+            ]
+            .into_iter(),
+        );
+        let found = sorted(grid.query_aabb(glm::vec2(0.0, 0.0), glm::vec2(1.0, 1.0)));
+        assert_eq!(found, vec![0, 1]);
+    }
+
+    #[test]
+    fn radius_leaves_out_its_edge() {
+        let mut grid = SpatialGrid::new(0.25);
+        grid.rebuild(
+            [
+                glm::vec2(0.5, 0.0),
+                glm::vec2(0.0, 0.4999),
+                // Inside the box around the circle, outside the circle.
+                glm::vec2(0.4, 0.4),
+            ]
+            .into_iter(),
+        );
+        let found = sorted(grid.query_radius(glm::vec2(0.0, 0.0), 0.5));
+        assert_eq!(found, vec![1]);
+    }
+
+    #[test]
+    fn negative_positions_land_in_their_own_cells() {
+        let mut grid = SpatialGrid::new(1.0);
+        grid.rebuild([glm::vec2(-0.5, -0.5), glm::vec2(0.5, 0.5)].into_iter());
+        let found = sorted(grid.query_radius(glm::vec2(-0.6, -0.6), 0.2));
+        assert_eq!(found, vec![0]);
+    }
+
+    #[test]
+    fn rebuild_forgets_old_positions() {
+        let mut grid = SpatialGrid::new(0.5);
+        grid.rebuild([glm::vec2(3.0, 3.0), glm::vec2(0.0, 0.0)].into_iter());
+        grid.rebuild([glm::vec2(0.1, 0.0)].into_iter());
+        assert_eq!(sorted(grid.query_radius(glm::vec2(3.0, 3.0), 1.0)), vec![]);
+        assert_eq!(sorted(grid.query_radius(glm::vec2(0.0, 0.0), 1.0)), vec![0]);
+    }
+
+    #[test]
+    fn matches_checking_everything() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let positions = (0..300)
+            .map(|_| glm::vec2(rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0)))
+            .collect::<Vec<_>>();
+        for cell_size in [0.25, 0.5, 1.0, 3.0] {
+            let mut grid = SpatialGrid::new(cell_size);
+            grid.rebuild(positions.iter().copied());
+            for _ in 0..100 {
+                let center = glm::vec2(rng.gen_range(-6.0..6.0), rng.gen_range(-6.0..6.0));
+                let radius = rng.gen_range(0.0..2.5);
+                let expected = (0..positions.len())
+                    .filter(|&i| glm::distance(&positions[i], &center) < radius)
+                    .collect::<Vec<_>>();
+                assert_eq!(sorted(grid.query_radius(center, radius)), expected);
+
+                let (min, max) = (center, center + glm::vec2(radius, radius * 0.5));
+                let expected = (0..positions.len())
+                    .filter(|&i| {
+                        let p = positions[i];
+                        p.x >= min.x && p.x <= max.x && p.y >= min.y && p.y <= max.y
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(sorted(grid.query_aabb(min, max)), expected);
+            }
+        }
+    }
+}
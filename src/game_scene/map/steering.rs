@@ -0,0 +1,116 @@
+use super::*;
+
+/// Kids start slowing down this far from where they're stopping.
+const ARRIVAL_RADIUS: f32 = 0.3;
+/// Kids closer together than this push apart.
+pub const SEPARATION_RADIUS: f32 = 0.28;
+/// Kids keep about this far from the player.
+const PLAYER_SPACE_RADIUS: f32 = 0.4;
+/// Kids this close to a house wall get pushed back off it. Smaller than the room paths keep
+/// from houses, so kids on a path don't fight it.
+const AVOID_MARGIN: f32 = 0.12;
+/// Houses further than this from a kid can't push it.
+pub const AVOID_RANGE: f32 = HOUSE_HALF_SIZE * std::f32::consts::SQRT_2 + AVOID_MARGIN;
+const SEPARATION_WEIGHT: f32 = 0.8;
+const PLAYER_SPACE_WEIGHT: f32 = 1.2;
+const AVOID_WEIGHT: f32 = 1.5;
+/// Room between kids waiting their turn at a door.
+const QUEUE_SPACING: f32 = 0.26;
+/// Kids further back in line than this go find another house.
+pub const QUEUE_LENGTH: usize = 5;
+
+/// Step towards `target` at up to `max_speed`, for waypoints along the way.
+pub fn seek(position: glm::Vec2, target: glm::Vec2, max_speed: f32) -> glm::Vec2 {
+    let offset = target - position;
+    let distance = glm::length(&offset);
+    if distance < f32::EPSILON {
+        return glm::Vec2::zeros();
+    }
+    offset / distance * max_speed.min(distance)
+}
+
+/// Like `seek`, but easing off to stop right on `target`.
+pub fn arrive(position: glm::Vec2, target: glm::Vec2, max_speed: f32) -> glm::Vec2 {
+    let distance = glm::distance(&position, &target);
+    seek(
+        position,
+        target,
+        max_speed * (distance / ARRIVAL_RADIUS).min(1.0),
+    )
+}
+
+/// Up to 1.0 away from `other`, more the closer it is. Falls back to `side` when right on
+/// top of it, so stacked kids don't all push the same way.
+fn push_away(position: glm::Vec2, other: glm::Vec2, radius: f32, side: glm::Vec2) -> glm::Vec2 {
+    let offset = position - other;
+    let distance = glm::length(&offset);
+    if distance >= radius {
+        return glm::Vec2::zeros();
+    }
+    let strength = 1.0 - distance / radius;
+    if distance < f32::EPSILON {
+        side * strength
+    } else {
+        offset / distance * strength
+    }
+}
+
+/// Spread out from the other kids around `position` and out of the player's way.
+pub fn separation(
+    position: glm::Vec2,
+    kids: impl Iterator<Item = glm::Vec2>,
+    player: glm::Vec2,
+    side: glm::Vec2,
+) -> glm::Vec2 {
+    let kids = kids
+        .map(|kid| push_away(position, kid, SEPARATION_RADIUS, side))
+        .sum::<glm::Vec2>();
+    kids * SEPARATION_WEIGHT
+        + push_away(position, player, PLAYER_SPACE_RADIUS, side) * PLAYER_SPACE_WEIGHT
+}
+
+/// Push off the walls of any house `position` is up against, or out of one it ended up in.
+pub fn avoid_houses(position: glm::Vec2, houses: impl Iterator<Item = glm::Vec2>) -> glm::Vec2 {
+    houses
+        .map(|house| {
+            let nearest = glm::vec2(
+                position
+                    .x
+                    .clamp(house.x - HOUSE_HALF_SIZE, house.x + HOUSE_HALF_SIZE),
+                position
+                    .y
+                    .clamp(house.y - HOUSE_HALF_SIZE, house.y + HOUSE_HALF_SIZE),
+            );
+            if nearest == position {
+                let out = position - house;
+                return if glm::length(&out) < f32::EPSILON {
+                    glm::vec2(0.0, -1.0)
+                } else {
+                    glm::normalize(&out)
+                };
+            }
+            push_away(position, nearest, AVOID_MARGIN, glm::Vec2::zeros())
+        })
+        .sum::<glm::Vec2>()
+        * AVOID_WEIGHT
+}
+
+/// Where the kid `place` spots back in line waits. The line fans out to either side of
+/// the door rather than trailing off into the street.
+pub fn queue_spot(door: glm::Vec2, place: usize) -> glm::Vec2 {
+    let row = place.div_ceil(2) as f32;
+    let side = if place % 2 == 1 { -1.0 } else { 1.0 };
+    door + glm::vec2(side * row * QUEUE_SPACING, -row * QUEUE_SPACING * 0.5)
+}
+
+/// Blend where a kid wants to go with the pushes from around it, into one step of at most
+/// `max_speed`.
+pub fn steer(desired: glm::Vec2, push: glm::Vec2, max_speed: f32) -> glm::Vec2 {
+    let step = desired + push * max_speed;
+    let length = glm::length(&step);
+    if length > max_speed {
+        step / length * max_speed
+    } else {
+        step
+    }
+}
@@ -0,0 +1,261 @@
+use super::*;
+
+const ATLAS_COLUMNS: usize = 4;
+const ATLAS_ROWS: usize = 2;
+const ATLAS_TILE_PIXELS: usize = 16;
+/// Tiles per chunk along each side.
+const CHUNK_SIZE: usize = 4;
+/// Half the size of the view in world units, see `RendererCamera`.
+const VIEW_EXTENT: f32 = 1.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TileId {
+    Grass,
+    Street,
+    Sidewalk,
+    Park,
+    Fence,
+    Flowers,
+    Leaves,
+}
+
+impl TileId {
+    const ALL: [TileId; 7] = [
+        TileId::Grass,
+        TileId::Street,
+        TileId::Sidewalk,
+        TileId::Park,
+        TileId::Fence,
+        TileId::Flowers,
+        TileId::Leaves,
+    ];
+
+    /// How the tile is written in level files. A space leaves the spot empty.
+    pub fn to_char(self) -> char {
+        match self {
+            TileId::Grass => '.',
+            TileId::Street => '#',
+            TileId::Sidewalk => '=',
+            TileId::Park => '"',
+            TileId::Fence => 'f',
+            TileId::Flowers => '*',
+            TileId::Leaves => 'l',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<TileId> {
+        TileId::ALL.into_iter().find(|tile| tile.to_char() == c)
+    }
+
+    fn uv(self) -> glm::Vec4 {
+        let i = self as usize;
+        let (w, h) = (1.0 / ATLAS_COLUMNS as f32, 1.0 / ATLAS_ROWS as f32);
+        // Half a texel in from the edges so neighbors in the atlas don't bleed in.
+        let inset = 0.5 / (ATLAS_COLUMNS * ATLAS_TILE_PIXELS) as f32;
+        glm::vec4(
+            (i % ATLAS_COLUMNS) as f32 * w + inset,
+            (i / ATLAS_COLUMNS) as f32 * h + inset,
+            w - inset * 2.0,
+            h - inset * 2.0,
+        )
+    }
+}
+
+/// The level's tiles, ready to draw and split into chunks so only the ones on screen are.
+#[derive(SingleResource, Default)]
+pub struct TileMap {
+    origin: glm::Vec2,
+    tile_size: f32,
+    chunks_x: usize,
+    chunks_y: usize,
+    /// Ground first, then decoration, so decoration draws on top.
+    chunks: Vec<Vec<Sprite>>,
+}
+
+impl TileMap {
+    pub fn new(tiles: &LevelTiles) -> Self {
+        let height = tiles.ground.len().max(tiles.decoration.len());
+        let width = tiles
+            .ground
+            .iter()
+            .chain(&tiles.decoration)
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let chunks_x = width.div_ceil(CHUNK_SIZE);
+        let chunks_y = height.div_ceil(CHUNK_SIZE);
+        let mut map = TileMap {
+            origin: tiles.origin,
+            tile_size: tiles.tile_size,
+            chunks_x,
+            chunks_y,
+            chunks: vec![vec![]; chunks_x * chunks_y],
+        };
+
+        for layer in [&tiles.ground, &tiles.decoration] {
+            // Rows are written top first.
+            for (row, line) in layer.iter().enumerate() {
+                let y = height - 1 - row;
+                for (x, c) in line.chars().enumerate() {
+                    if let Some(tile) = TileId::from_char(c) {
+                        let sprite = map.sprite(tile, x, y);
+                        map.chunks[(y / CHUNK_SIZE) * chunks_x + x / CHUNK_SIZE].push(sprite);
+                    }
+                }
+            }
+        }
+        map
+    }
+
+    fn sprite(&self, tile: TileId, x: usize, y: usize) -> Sprite {
+        Sprite {
+            texture_index: SPRITE_TILES,
+            position: self.origin + glm::vec2(x as f32 + 0.5, y as f32 + 0.5) * self.tile_size,
+            scale: glm::vec2(self.tile_size, self.tile_size),
+            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
+            uv: tile.uv(),
+            ..Default::default()
+        }
+    }
+
+    /// Range of chunks along one axis that overlap `min..max` in world space.
+    fn chunk_range(
+        &self,
+        min: f32,
+        max: f32,
+        origin: f32,
+        chunks: usize,
+    ) -> std::ops::Range<usize> {
+        let chunk_size = self.tile_size * CHUNK_SIZE as f32;
+        let first = ((min - origin) / chunk_size).floor().max(0.0) as usize;
+        let last = (((max - origin) / chunk_size).floor() + 1.0).max(0.0) as usize;
+        first.min(chunks)..last.min(chunks)
+    }
+}
+
+pub fn tile_map_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(
+        TileMap::single_resource(),
+        TileMap::new(&level(galaxy).tiles),
+    );
+}
+
+pub fn tile_map_rebuild(galaxy: &Galaxy) {
+    let tiles = level(galaxy).tiles;
+    **galaxy
+        .get_mut_resource::<TileMap, _>(TileMap::single_resource())
+        .unwrap() = TileMap::new(&tiles);
+}
+
+pub fn tile_map_render_update(galaxy: &Galaxy) {
+    let map = galaxy
+        .get_resource::<TileMap, _>(TileMap::single_resource())
+        .unwrap();
+    if map.chunks.is_empty() {
+        return;
+    }
+    let camera = galaxy
+        .get_resource::<RendererCamera, _>(RendererCamera::single_resource())
+        .unwrap()
+        .position;
+
+    let margin = VIEW_EXTENT + map.tile_size;
+    let xs = map.chunk_range(
+        camera.x - margin,
+        camera.x + margin,
+        map.origin.x,
+        map.chunks_x,
+    );
+    let ys = map.chunk_range(
+        camera.y - margin,
+        camera.y + margin,
+        map.origin.y,
+        map.chunks_y,
+    );
+    for cy in ys {
+        for cx in xs.clone() {
+            for &sprite in &map.chunks[cy * map.chunks_x + cx] {
+                galaxy.insert_event(RendererDrawSprite(sprite));
+            }
+        }
+    }
+}
+
+/// Small value noise, so the atlas doesn't come out flat.
+fn speckle(x: f32, y: f32) -> f32 {
+    let px = ((x + 1.0) * 64.0) as u32;
+    let py = ((y + 1.0) * 64.0) as u32;
+    let mut h = px.wrapping_mul(0x27d4_eb2d) ^ py.wrapping_mul(0x1656_67b1);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    (h & 0xff) as f32 / 255.0
+}
+
+fn shade(base: [u8; 3], amount: f32) -> [u8; 4] {
+    let scale = 1.0 + (amount - 0.5) * 0.25;
+    [
+        (base[0] as f32 * scale).min(255.0) as u8,
+        (base[1] as f32 * scale).min(255.0) as u8,
+        (base[2] as f32 * scale).min(255.0) as u8,
+        255,
+    ]
+}
+
+fn tile_pixel(tile: TileId, x: f32, y: f32, noise: f32) -> [u8; 4] {
+    match tile {
+        TileId::Grass => shade([58, 92, 48], noise),
+        TileId::Park => shade([44, 104, 44], noise),
+        TileId::Street => shade([70, 70, 76], noise),
+        TileId::Sidewalk => {
+            if x.abs() > 0.92 || y.abs() > 0.92 {
+                [110, 110, 112, 255]
+            } else {
+                shade([150, 148, 140], noise)
+            }
+        }
+        TileId::Fence => {
+            let post = (x * 2.0).rem_euclid(1.0) < 0.2;
+            let rail = (y - 0.3).abs() < 0.08 || (y + 0.3).abs() < 0.08;
+            if y.abs() < 0.7 && (post || rail) {
+                [120, 84, 52, 255]
+            } else {
+                [0, 0, 0, 0]
+            }
+        }
+        TileId::Flowers => {
+            if noise > 0.93 {
+                [230, 120, 160, 255]
+            } else if noise > 0.88 {
+                [240, 220, 90, 255]
+            } else {
+                [0, 0, 0, 0]
+            }
+        }
+        TileId::Leaves => {
+            if noise > 0.85 {
+                [200, 100 + (noise * 60.0) as u8, 30, 255]
+            } else {
+                [0, 0, 0, 0]
+            }
+        }
+    }
+}
+
+pub fn tile_atlas_texture() -> TextureData {
+    let width = ATLAS_COLUMNS * ATLAS_TILE_PIXELS;
+    let height = ATLAS_ROWS * ATLAS_TILE_PIXELS;
+    TextureData::from_fn(width, height, |x, y| {
+        // Which atlas cell this is, and where inside it from -1.0 to 1.0.
+        let u = (x + 1.0) * 0.5 * ATLAS_COLUMNS as f32;
+        let v = (1.0 - y) * 0.5 * ATLAS_ROWS as f32;
+        let i =
+            (v as usize).min(ATLAS_ROWS - 1) * ATLAS_COLUMNS + (u as usize).min(ATLAS_COLUMNS - 1);
+        let local_x = u.fract() * 2.0 - 1.0;
+        let local_y = 1.0 - v.fract() * 2.0;
+        match TileId::ALL.get(i) {
+            Some(&tile) => tile_pixel(tile, local_x, local_y, speckle(x, y)),
+            None => [0, 0, 0, 0],
+        }
+    })
+}
@@ -0,0 +1,122 @@
+use super::*;
+
+const TITLE_Y: f32 = 0.45;
+const TITLE_SIZE: f32 = 0.16;
+const ITEM_START_Y: f32 = 0.2;
+const ITEM_SPACING: f32 = 0.13;
+const ITEMS_HEIGHT: f32 = 1.0;
+const ITEM_SIZE: f32 = 0.09;
+const TITLE_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 1.0, 1.0, 1.0);
+const ITEM_COLOR: glm::Vec4 = glm::Vec4::new(0.7, 0.7, 0.7, 1.0);
+const SELECTED_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 0.55, 0.1, 1.0);
+const DISABLED_COLOR: glm::Vec4 = glm::Vec4::new(0.3, 0.3, 0.3, 1.0);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+}
+
+/// Menus act on key presses, so releases left over from the previous screen are ignored.
+/// Back is the exception: it acts on release, so the press can't leak into the screen below.
+pub fn menu_input(input: &KeyboardInput) -> Option<MenuInput> {
+    let key = input.virtual_keycode?;
+    match input.state {
+        ElementState::Pressed => match key {
+            VirtualKeyCode::W | VirtualKeyCode::Up => Some(MenuInput::Up),
+            VirtualKeyCode::S | VirtualKeyCode::Down => Some(MenuInput::Down),
+            VirtualKeyCode::A | VirtualKeyCode::Left => Some(MenuInput::Left),
+            VirtualKeyCode::D | VirtualKeyCode::Right => Some(MenuInput::Right),
+            VirtualKeyCode::Return | VirtualKeyCode::Space => Some(MenuInput::Select),
+            _ => None,
+        },
+        ElementState::Released => (key == VirtualKeyCode::Escape).then_some(MenuInput::Back),
+    }
+}
+
+pub struct MenuItem {
+    pub label: String,
+    pub enabled: bool,
+}
+
+impl MenuItem {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            enabled: true,
+        }
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+}
+
+/// Move `selected` for up and down inputs, skipping disabled items.
+pub fn menu_navigate(selected: &mut usize, items: &[MenuItem], input: MenuInput) {
+    let step = match input {
+        MenuInput::Up => items.len() - 1,
+        MenuInput::Down => 1,
+        _ => return,
+    };
+    for _ in 0..items.len() {
+        *selected = (*selected + step) % items.len();
+        if items[*selected].enabled {
+            return;
+        }
+    }
+}
+
+/// Draws a dimmed backdrop with `title` and `items` centered on the camera.
+pub fn draw_menu(
+    galaxy: &Galaxy,
+    title: &str,
+    items: &[MenuItem],
+    selected: usize,
+    backdrop_alpha: f32,
+) {
+    let camera = *galaxy
+        .get_resource::<RendererCamera, _>(RendererCamera::single_resource())
+        .unwrap();
+
+    galaxy.insert_event(RendererDrawSprite(Sprite {
+        texture_index: SPRITE_WHITE,
+        color: glm::vec4(0.0, 0.0, 0.0, backdrop_alpha),
+        scale: glm::vec2(4.0, 4.0),
+        position: camera.position,
+        unlit: true,
+        ..Default::default()
+    }));
+
+    draw_text(
+        galaxy,
+        title,
+        camera.position + glm::vec2(0.0, TITLE_Y),
+        TITLE_SIZE,
+        TITLE_COLOR,
+    );
+
+    // Long menus squeeze together rather than running off the bottom of the screen.
+    let spacing = ITEM_SPACING.min(ITEMS_HEIGHT / items.len() as f32);
+    for (i, item) in items.iter().enumerate() {
+        let (label, color) = if !item.enabled {
+            (item.label.clone(), DISABLED_COLOR)
+        } else if i == selected {
+            (format!("> {} <", item.label), SELECTED_COLOR)
+        } else {
+            (item.label.clone(), ITEM_COLOR)
+        };
+        draw_text(
+            galaxy,
+            &label,
+            camera.position + glm::vec2(0.0, ITEM_START_Y - i as f32 * spacing),
+            ITEM_SIZE,
+            color,
+        );
+    }
+}
@@ -1,25 +1,69 @@
 use super::*;
-use instant::Instant;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
 mod candyland;
+mod clock;
+mod high_scores;
+mod input;
 mod intro;
 mod load;
 mod map;
+mod menu;
+mod new_game;
+mod notoriety;
 mod outro;
+mod particle;
+mod pause;
+mod replay_hud;
+mod replay_setup;
+mod rng;
+mod round;
+mod rules;
+mod save;
 mod score;
+mod settings;
+mod state;
+mod stats;
+mod text;
+mod transition;
+mod tuning;
 
+use clock::*;
+use high_scores::{high_scores_exist, high_scores_record};
+use input::*;
 use load::*;
-use score::{spawn_score_decrement, spawn_score_increment};
+use menu::*;
+use new_game::new_game;
+use notoriety::*;
+use particle::*;
+use pause::{pause_open, PausePage};
+use rng::*;
+use round::{round_draw_stars, RoundResult};
+use rules::*;
+use save::{continue_game, save_clear, save_exists, save_game};
+use score::{hud_offset, spawn_score_decrement, spawn_score_increment};
+use settings::{key_bindings, settings_rules, Difficulty};
+use state::*;
+use stats::*;
+use text::*;
+use transition::*;
+use tuning::*;
 
-pub use map::Player;
+pub use map::{HouseKind, Player};
 
-#[derive(SingleResource, Clone, Copy, PartialEq, Eq)]
+#[derive(SingleResource, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum GameState {
     Intro,
     Outro,
+    GameOver,
     Map,
     CandyLand,
+    Pause,
+    Settings,
+    HighScores,
+    /// Debug builds only, see `map::editor`.
+    Editor,
 }
 
 pub fn run() {
@@ -33,46 +77,135 @@ pub fn run() {
         &[
             graphics::graphics_update,
             game_quit,
-            game_state_gate_update,
+            replay_setup::replay_setup_update,
+            tuning::tuning_reload_update,
+            clock::game_clock_update,
+            notoriety::notoriety_update,
+            state::game_state_machine_update,
+            stats::stats_update,
+            round::round_update,
+            particle::particle_emitter_update,
+            particle::particle_update,
             score::score_render_update,
             score::score_increments_update,
+            round::round_render_update,
+            notoriety::notoriety_render_update,
+            replay_hud::replay_render_update,
+            transition::transition_update,
         ],
+        // Closing the window mid-run keeps it for Continue, same as quitting from the menu.
+        &[save_game],
     );
 }
 
 fn game_state_gate_init(galaxy: &Galaxy) {
+    settings::settings_init(galaxy);
+    tuning_init(galaxy);
+    rng_init(galaxy);
+    rules_init(galaxy);
+    replay_setup::replay_setup_init(galaxy);
+    state_machine_init(galaxy);
+    game_clock_init(galaxy);
+    stats_init(galaxy);
+    notoriety_init(galaxy);
+    high_scores::high_scores_init(galaxy);
+    transition_init(galaxy);
+    pause::pause_init(galaxy);
+    intro::intro_init(galaxy);
+    outro::outro_init(galaxy);
+    round::round_init(galaxy);
     map::map_init(galaxy);
     candyland::candyland_init(galaxy);
-}
+    #[cfg(debug_assertions)]
+    map::editor_init(galaxy);
 
-fn game_state_gate_update(galaxy: &Galaxy) {
-    if let Some(state) = galaxy.get_resource::<GameState, _>(GameState::single_resource()) {
-        match *state {
-            GameState::Intro => {
-                drop(state);
-                intro::intro_update(galaxy);
-            }
-            GameState::Outro => {
-                drop(state);
-                outro::outro_update(galaxy);
-            }
-            GameState::Map => {
-                drop(state);
-                map::map_update(galaxy);
-            }
-            GameState::CandyLand => {
-                drop(state);
-                candyland::candyland_update(galaxy);
-            }
-        }
-    }
+    state_register(
+        galaxy,
+        GameState::Intro,
+        GameStateHooks::new(intro::intro_update)
+            .on_enter(intro::intro_enter)
+            .on_render(intro::intro_render),
+    );
+    state_register(
+        galaxy,
+        GameState::Outro,
+        GameStateHooks::new(outro::outro_update)
+            .on_enter(outro::outro_enter)
+            .on_render(outro::outro_render),
+    );
+    state_register(
+        galaxy,
+        GameState::GameOver,
+        GameStateHooks::new(outro::outro_update)
+            .on_enter(outro::outro_enter)
+            .on_render(outro::game_over_render),
+    );
+    state_register(
+        galaxy,
+        GameState::Map,
+        GameStateHooks::new(map::map_update).on_render(map::map_render),
+    );
+    state_register(
+        galaxy,
+        GameState::CandyLand,
+        GameStateHooks::new(candyland::candyland_update)
+            .on_enter(candyland::candyland_enter)
+            .on_exit(candyland::candyland_reset)
+            .on_render(candyland::candyland_render),
+    );
+    state_register(
+        galaxy,
+        GameState::Pause,
+        GameStateHooks::new(pause::pause_update).on_render(pause::pause_render),
+    );
+    state_register(
+        galaxy,
+        GameState::Settings,
+        GameStateHooks::new(settings::settings_update)
+            .on_enter(settings::settings_enter)
+            .on_exit(settings::settings_exit)
+            .on_render(settings::settings_render),
+    );
+    state_register(
+        galaxy,
+        GameState::HighScores,
+        GameStateHooks::new(high_scores::high_scores_update)
+            .on_render(high_scores::high_scores_render),
+    );
+    #[cfg(debug_assertions)]
+    state_register(
+        galaxy,
+        GameState::Editor,
+        GameStateHooks::new(map::editor_update)
+            .on_enter(map::editor_enter)
+            .on_render(map::editor_render),
+    );
+
+    state_set(galaxy, GameState::Intro);
 }
 
+/// Q asks for confirmation during gameplay, quits straight away from the title and end screens,
+/// and is left alone in menus.
 fn game_quit(galaxy: &Galaxy) {
+    let state = *galaxy
+        .get_resource::<GameState, _>(GameState::single_resource())
+        .unwrap();
+
     for ev in galaxy.get_events::<WindowEvent>() {
         if let WindowEventData::KeyboardInput { input, .. } = ev.0 {
             if input.virtual_keycode == Some(VirtualKeyCode::Q) {
-                galaxy.set_exit();
+                match state {
+                    GameState::Map | GameState::CandyLand => {
+                        if input.state == ElementState::Released && !transition_active(galaxy) {
+                            pause_open(galaxy, PausePage::ConfirmQuit);
+                        }
+                    }
+                    GameState::Intro | GameState::Outro | GameState::GameOver => galaxy.set_exit(),
+                    GameState::Pause
+                    | GameState::Settings
+                    | GameState::HighScores
+                    | GameState::Editor => (),
+                }
             }
         }
     }
@@ -0,0 +1,18 @@
+use super::*;
+
+/// Throw away the current run and fade into a freshly generated one.
+/// Returns `false` if a transition is already running.
+pub fn new_game(galaxy: &Galaxy) -> bool {
+    transition_to_then(galaxy, TransitionKind::Fade, GameState::Map, new_game_reset)
+}
+
+fn new_game_reset(galaxy: &Galaxy) {
+    rules_reset(galaxy);
+    rng_reset(galaxy);
+    map::map_reset(galaxy);
+    candyland::candyland_reset(galaxy);
+    score::score_reset(galaxy);
+    particle::particle_reset(galaxy);
+    stats_reset(galaxy);
+    notoriety_reset(galaxy);
+}
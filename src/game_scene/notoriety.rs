@@ -0,0 +1,160 @@
+use super::*;
+
+pub const NOTORIETY_MAX: f32 = 100.0;
+const METER_X: f32 = 0.65;
+const METER_Y: f32 = 0.7;
+const METER_WIDTH: f32 = 0.4;
+const METER_HEIGHT: f32 = 0.035;
+const METER_BACK_COLOR: glm::Vec4 = glm::Vec4::new(0.15, 0.15, 0.18, 0.8);
+const METER_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 0.7, 0.2, 1.0);
+/// Once houses stop opening the door.
+const METER_REFUSE_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 0.25, 0.2, 1.0);
+const METER_MARK_COLOR: glm::Vec4 = glm::Vec4::new(0.9, 0.9, 0.9, 1.0);
+const LABEL_OFFSET: f32 = 0.06;
+const LABEL_SIZE: f32 = 0.04;
+
+/// How much the neighborhood has noticed the player's behavior, up to `NOTORIETY_MAX`.
+#[derive(SingleResource, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct Notoriety {
+    level: f32,
+    /// It only starts to die down a while after the last incident.
+    last_incident: GameInstant,
+    last_update: GameInstant,
+}
+
+impl Notoriety {
+    /// How many neighborhood watch adults should be out patrolling.
+    pub fn watchers(&self, tuning: &NotorietyTuning) -> usize {
+        tuning
+            .watch_thresholds
+            .iter()
+            .filter(|&&threshold| self.level >= threshold)
+            .count()
+    }
+
+    pub fn houses_refuse(&self, tuning: &NotorietyTuning) -> bool {
+        self.level >= tuning.refuse_threshold
+    }
+}
+
+pub fn notoriety_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(Notoriety::single_resource(), Notoriety::default());
+}
+
+pub fn notoriety_reset(galaxy: &Galaxy) {
+    let now = game_now(galaxy);
+    notoriety_restore(
+        galaxy,
+        Notoriety {
+            level: 0.0,
+            last_incident: now,
+            last_update: now,
+        },
+    );
+}
+
+pub fn notoriety_restore(galaxy: &Galaxy, notoriety: Notoriety) {
+    **galaxy
+        .get_mut_resource::<Notoriety, _>(Notoriety::single_resource())
+        .unwrap() = notoriety;
+}
+
+pub fn notoriety(galaxy: &Galaxy) -> Notoriety {
+    *galaxy
+        .get_resource::<Notoriety, _>(Notoriety::single_resource())
+        .unwrap()
+}
+
+/// The player got caught doing something the neighbors won't forget soon.
+pub fn notoriety_raise(galaxy: &Galaxy, amount: f32) {
+    let mut notoriety = galaxy
+        .get_mut_resource::<Notoriety, _>(Notoriety::single_resource())
+        .unwrap();
+    notoriety.level = (notoriety.level + amount).min(NOTORIETY_MAX);
+    notoriety.last_incident = game_now(galaxy);
+}
+
+/// Settle some of it without waiting, like paying back candy.
+pub fn notoriety_lower(galaxy: &Galaxy, amount: f32) {
+    let mut notoriety = galaxy
+        .get_mut_resource::<Notoriety, _>(Notoriety::single_resource())
+        .unwrap();
+    notoriety.level = (notoriety.level - amount).max(0.0);
+}
+
+/// Goes by game time, so nothing gets forgotten while paused.
+pub fn notoriety_update(galaxy: &Galaxy) {
+    let now = game_now(galaxy);
+    let tuning = tuning(galaxy).notoriety;
+    let mut notoriety = galaxy
+        .get_mut_resource::<Notoriety, _>(Notoriety::single_resource())
+        .unwrap();
+
+    let elapsed = now.duration_since(notoriety.last_update);
+    notoriety.last_update = now;
+    if now.duration_since(notoriety.last_incident) > tuning.decay_delay {
+        notoriety.level =
+            (notoriety.level - tuning.decay_per_second * elapsed.as_secs_f32()).max(0.0);
+    }
+}
+
+fn meter_sprite(position: glm::Vec2, width: f32, color: glm::Vec4) -> Sprite {
+    Sprite {
+        texture_index: SPRITE_WHITE,
+        position,
+        scale: glm::vec2(width, METER_HEIGHT),
+        color,
+        unlit: true,
+        ..Default::default()
+    }
+}
+
+/// A bar under the clock, with a mark for every watch adult that comes out along the way.
+/// Hidden while the neighborhood has nothing on the player.
+pub fn notoriety_render_update(galaxy: &Galaxy) {
+    let state = *galaxy
+        .get_resource::<GameState, _>(GameState::single_resource())
+        .unwrap();
+    if !state_on_stack(galaxy, GameState::Map) && !state_on_stack(galaxy, GameState::CandyLand) {
+        return;
+    }
+    let notoriety = notoriety(galaxy);
+    if notoriety.level <= 0.0 {
+        return;
+    }
+
+    let tuning = tuning(galaxy).notoriety;
+    let center = glm::vec2(METER_X, METER_Y) + hud_offset(galaxy, state);
+    let left = center.x - METER_WIDTH * 0.5;
+    let fill = METER_WIDTH * notoriety.level / NOTORIETY_MAX;
+
+    galaxy.insert_event(RendererDrawSprite(meter_sprite(
+        center,
+        METER_WIDTH,
+        METER_BACK_COLOR,
+    )));
+    galaxy.insert_event(RendererDrawSprite(meter_sprite(
+        glm::vec2(left + fill * 0.5, center.y),
+        fill,
+        if notoriety.houses_refuse(&tuning) {
+            METER_REFUSE_COLOR
+        } else {
+            METER_COLOR
+        },
+    )));
+    for threshold in tuning.watch_thresholds {
+        let x = left + METER_WIDTH * threshold / NOTORIETY_MAX;
+        galaxy.insert_event(RendererDrawSprite(meter_sprite(
+            glm::vec2(x, center.y),
+            0.006,
+            METER_MARK_COLOR,
+        )));
+    }
+    draw_text(
+        galaxy,
+        "NOTORIETY",
+        center + glm::vec2(0.0, LABEL_OFFSET),
+        LABEL_SIZE,
+        METER_MARK_COLOR,
+    );
+}
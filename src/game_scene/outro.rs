@@ -1,16 +1,132 @@
 use super::*;
 
-pub fn outro_update(galaxy: &Galaxy) {
+const BACKDROP_ALPHA: f32 = 0.0;
+const GAME_OVER_BACKDROP_ALPHA: f32 = 1.0;
+const STARS_Y: f32 = 0.45;
+const RESULT_Y: f32 = 0.32;
+const RESULT_SIZE: f32 = 0.07;
+const RESULT_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 0.8, 0.5, 1.0);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OutroItem {
+    PlayAgain,
+    HighScores,
+    Title,
+}
+
+const OUTRO_ITEMS: [OutroItem; 3] = [
+    OutroItem::PlayAgain,
+    OutroItem::HighScores,
+    OutroItem::Title,
+];
+
+#[derive(SingleResource, Default, Clone, Copy)]
+struct OutroMenu {
+    selected: usize,
+}
+
+fn outro_menu_items() -> Vec<MenuItem> {
+    OUTRO_ITEMS
+        .iter()
+        .map(|item| match item {
+            OutroItem::PlayAgain => MenuItem::new("Play Again"),
+            OutroItem::HighScores => MenuItem::new("High Scores"),
+            OutroItem::Title => MenuItem::new("Title Screen"),
+        })
+        .collect()
+}
+
+pub fn outro_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(OutroMenu::single_resource(), OutroMenu::default());
+}
+
+pub fn outro_enter(galaxy: &Galaxy) {
     galaxy
         .get_mut_resource::<RendererCamera, _>(RendererCamera::single_resource())
         .unwrap()
         .position = glm::vec2(0.0, 0.0);
+    galaxy
+        .get_mut_resource::<OutroMenu, _>(OutroMenu::single_resource())
+        .unwrap()
+        .selected = 0;
+}
+
+pub fn outro_update(galaxy: &Galaxy) {
+    let mut menu = galaxy
+        .get_mut_resource::<OutroMenu, _>(OutroMenu::single_resource())
+        .unwrap();
+    let items = outro_menu_items();
+
+    for input in keyboard_inputs(galaxy) {
+        let Some(input) = menu_input(&input) else {
+            continue;
+        };
+        let item = match input {
+            MenuInput::Select => OUTRO_ITEMS[menu.selected],
+            MenuInput::Back => OutroItem::Title,
+            _ => {
+                menu_navigate(&mut menu.selected, &items, input);
+                continue;
+            }
+        };
+
+        match item {
+            OutroItem::PlayAgain => {
+                new_game(galaxy);
+            }
+            OutroItem::HighScores => state_push(galaxy, GameState::HighScores),
+            OutroItem::Title => {
+                transition_to(galaxy, TransitionKind::Fade, GameState::Intro);
+            }
+        }
+    }
+}
+
+pub fn outro_render(galaxy: &Galaxy) {
     galaxy.insert_event(RendererDrawSprite(Sprite {
-        visible: 1.0,
         texture_index: SPRITE_OUTRO,
-        color: glm::vec3(1.0, 1.0, 1.0),
+        color: glm::vec4(1.0, 1.0, 1.0, 1.0),
         rotation: 0.0,
         scale: glm::vec2(1.9, 1.9),
         position: glm::vec2(0.0, 0.0),
+        unlit: true,
+        ..Default::default()
     }));
+
+    let menu = *galaxy
+        .get_resource::<OutroMenu, _>(OutroMenu::single_resource())
+        .unwrap();
+    draw_menu(
+        galaxy,
+        "",
+        &outro_menu_items(),
+        menu.selected,
+        BACKDROP_ALPHA,
+    );
+    round_draw_stars(galaxy, glm::vec2(0.0, STARS_Y));
+}
+
+/// The outro menu over a plain backdrop, for nights that end short of the target.
+pub fn game_over_render(galaxy: &Galaxy) {
+    let menu = *galaxy
+        .get_resource::<OutroMenu, _>(OutroMenu::single_resource())
+        .unwrap();
+    let result = *galaxy
+        .get_resource::<RoundResult, _>(RoundResult::single_resource())
+        .unwrap();
+
+    draw_menu(
+        galaxy,
+        "MIDNIGHT!",
+        &outro_menu_items(),
+        menu.selected,
+        GAME_OVER_BACKDROP_ALPHA,
+    );
+    draw_text(
+        galaxy,
+        &format!("{} of {} candy", result.score, rules(galaxy).win_score),
+        glm::vec2(0.0, RESULT_Y),
+        RESULT_SIZE,
+        RESULT_COLOR,
+    );
 }
@@ -0,0 +1,350 @@
+use super::*;
+
+const MAX_PARTICLES: usize = 256;
+const MAX_CURVE_KEYS: usize = 4;
+
+/// Piecewise linear curve sampled over a particle's lifetime (0.0 to 1.0).
+#[derive(Clone, Copy)]
+pub struct ParticleCurve<T> {
+    keys: [(f32, T); MAX_CURVE_KEYS],
+    len: usize,
+}
+
+impl<T> ParticleCurve<T>
+where
+    T: Copy + std::ops::Add<Output = T> + std::ops::Mul<f32, Output = T>,
+{
+    pub fn constant(value: T) -> Self {
+        Self {
+            keys: [(0.0, value); MAX_CURVE_KEYS],
+            len: 1,
+        }
+    }
+
+    pub fn linear(from: T, to: T) -> Self {
+        Self::constant(from).key(1.0, to)
+    }
+
+    /// Keys must be added in increasing `t` order.
+    pub fn key(mut self, t: f32, value: T) -> Self {
+        assert!(self.len < MAX_CURVE_KEYS, "Too many particle curve keys");
+        self.keys[self.len] = (t, value);
+        self.len += 1;
+        self
+    }
+
+    pub fn sample(&self, t: f32) -> T {
+        let keys = &self.keys[..self.len];
+        if t <= keys[0].0 {
+            return keys[0].1;
+        }
+        for pair in keys.windows(2) {
+            let (t0, v0) = pair[0];
+            let (t1, v1) = pair[1];
+            if t <= t1 {
+                let f = (t - t0) / (t1 - t0).max(f32::EPSILON);
+                return v0 * (1.0 - f) + v1 * f;
+            }
+        }
+        keys[keys.len() - 1].1
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ParticleEmitterConfig {
+    pub texture_index: usize,
+    pub blend: SpriteBlend,
+    /// Particles per second, emitted on top of `burst`.
+    pub spawn_rate: f32,
+    /// Particles emitted all at once when the emitter spawns.
+    pub burst: usize,
+    /// How long the emitter lives. `None` emits forever.
+    pub duration: Option<Duration>,
+    pub lifetime: Duration,
+    pub spawn_area: glm::Vec2,
+    pub velocity: glm::Vec2,
+    pub velocity_spread: glm::Vec2,
+    pub gravity: glm::Vec2,
+    pub spin: f32,
+    pub color: ParticleCurve<glm::Vec4>,
+    pub scale: ParticleCurve<f32>,
+    /// Treat the emitter position as an offset from the camera.
+    pub follow_camera: bool,
+}
+
+impl ParticleEmitterConfig {
+    pub fn punch_stars() -> Self {
+        Self {
+            texture_index: SPRITE_PARTICLE_STAR,
+            blend: SpriteBlend::Additive,
+            spawn_rate: 0.0,
+            burst: 8,
+            duration: Some(Duration::ZERO),
+            lifetime: Duration::from_millis(450),
+            spawn_area: glm::vec2(0.1, 0.1),
+            velocity: glm::vec2(0.0, 0.0),
+            velocity_spread: glm::vec2(0.02, 0.02),
+            gravity: glm::vec2(0.0, -0.0006),
+            spin: 0.2,
+            color: ParticleCurve::linear(
+                glm::vec4(1.0, 0.95, 0.3, 1.0),
+                glm::vec4(1.0, 0.4, 0.1, 0.0),
+            ),
+            scale: ParticleCurve::linear(0.12, 0.0),
+            follow_camera: false,
+        }
+    }
+
+    pub fn candy_sparkle() -> Self {
+        Self {
+            texture_index: SPRITE_PARTICLE_STAR,
+            blend: SpriteBlend::Additive,
+            spawn_rate: 0.0,
+            burst: 6,
+            duration: Some(Duration::ZERO),
+            lifetime: Duration::from_millis(600),
+            spawn_area: glm::vec2(0.15, 0.15),
+            velocity: glm::vec2(0.0, 0.002),
+            velocity_spread: glm::vec2(0.008, 0.008),
+            gravity: glm::vec2(0.0, 0.0002),
+            spin: 0.1,
+            color: ParticleCurve::linear(
+                glm::vec4(1.0, 1.0, 1.0, 1.0),
+                glm::vec4(1.0, 0.5, 0.8, 0.0),
+            ),
+            scale: ParticleCurve::constant(0.06).key(0.3, 0.09).key(1.0, 0.0),
+            follow_camera: false,
+        }
+    }
+
+    pub fn door_dust() -> Self {
+        Self {
+            texture_index: SPRITE_PARTICLE_SOFT,
+            blend: SpriteBlend::Alpha,
+            spawn_rate: 0.0,
+            burst: 14,
+            duration: Some(Duration::ZERO),
+            lifetime: Duration::from_millis(900),
+            spawn_area: glm::vec2(0.6, 0.05),
+            velocity: glm::vec2(0.0, 0.004),
+            velocity_spread: glm::vec2(0.012, 0.004),
+            gravity: glm::vec2(0.0, -0.0002),
+            spin: 0.02,
+            color: ParticleCurve::linear(
+                glm::vec4(0.6, 0.55, 0.5, 0.8),
+                glm::vec4(0.3, 0.28, 0.26, 0.0),
+            ),
+            scale: ParticleCurve::constant(0.1).key(0.7, 0.25).key(1.0, 0.0),
+            follow_camera: false,
+        }
+    }
+
+    pub fn autumn_leaves() -> Self {
+        Self {
+            texture_index: SPRITE_PARTICLE_LEAF,
+            blend: SpriteBlend::Alpha,
+            spawn_rate: 5.0,
+            burst: 0,
+            duration: None,
+            lifetime: Duration::from_secs(7),
+            spawn_area: glm::vec2(3.0, 0.0),
+            velocity: glm::vec2(0.0, -0.004),
+            velocity_spread: glm::vec2(0.004, 0.001),
+            gravity: glm::vec2(0.00001, 0.0),
+            spin: 0.03,
+            color: ParticleCurve::constant(glm::vec4(0.9, 0.45, 0.1, 1.0))
+                .key(0.8, glm::vec4(0.5, 0.25, 0.08, 1.0))
+                .key(1.0, glm::vec4(0.5, 0.25, 0.08, 0.0)),
+            scale: ParticleCurve::constant(0.08),
+            follow_camera: true,
+        }
+    }
+}
+
+#[derive(CheapComponent, Clone, Copy)]
+pub struct ParticleEmitter {
+    position: glm::Vec2,
+    state: GameState,
+    config: ParticleEmitterConfig,
+    spawned: GameInstant,
+    last_emit: GameInstant,
+    pending: f32,
+}
+
+#[derive(CheapComponent, Clone, Copy)]
+pub struct Particle {
+    position: glm::Vec2,
+    velocity: glm::Vec2,
+    rotation: f32,
+    spin: f32,
+    spawned: GameInstant,
+    state: GameState,
+    config: ParticleEmitterConfig,
+}
+
+impl Particle {
+    pub fn as_sprite(&self, life: f32) -> Sprite {
+        let scale = self.config.scale.sample(life);
+        Sprite {
+            texture_index: self.config.texture_index,
+            color: self.config.color.sample(life),
+            blend: self.config.blend,
+            rotation: self.rotation,
+            scale: glm::vec2(scale, scale),
+            position: self.position,
+            unlit: false,
+            ..Default::default()
+        }
+    }
+}
+
+/// Particles only update while `state` is the current `GameState`, and render while it's on the stack.
+pub fn spawn_particle_emitter(
+    galaxy: &Galaxy,
+    state: GameState,
+    position: glm::Vec2,
+    config: ParticleEmitterConfig,
+) {
+    let now = game_now(galaxy);
+    galaxy.insert_entity().insert(ParticleEmitter {
+        position,
+        state,
+        config,
+        spawned: now,
+        last_emit: now,
+        pending: config.burst as f32,
+    });
+}
+
+/// Drop every live particle along with one-shot emitters. Emitters that run forever stay.
+pub fn particle_reset(galaxy: &Galaxy) {
+    for (e, _) in galaxy.query::<&Particle>().eiter() {
+        galaxy.remove_entity(e);
+    }
+    for (e, emitter) in galaxy.query::<&ParticleEmitter>().eiter() {
+        if emitter.config.duration.is_some() {
+            galaxy.remove_entity(e);
+        }
+    }
+}
+
+fn spread(rng: &mut StdRng, extent: f32) -> f32 {
+    (rng.gen::<f32>() - 0.5) * 2.0 * extent
+}
+
+pub fn particle_emitter_update(galaxy: &Galaxy) {
+    let mut rng = game_rng(galaxy, RngStream::Particles);
+    let camera = *galaxy
+        .get_resource::<RendererCamera, _>(RendererCamera::single_resource())
+        .unwrap();
+    let state = *galaxy
+        .get_resource::<GameState, _>(GameState::single_resource())
+        .unwrap();
+
+    let mut alive = galaxy.query::<&Particle>().iter().count();
+    let now = game_now(galaxy);
+
+    for (e, emitter) in galaxy.query::<&mut ParticleEmitter>().eiter() {
+        let config = emitter.config;
+
+        emitter.pending += config.spawn_rate * now.duration_since(emitter.last_emit).as_secs_f32();
+        emitter.last_emit = now;
+
+        let count = emitter.pending.floor();
+        emitter.pending -= count;
+
+        if emitter.state == state {
+            let origin = if config.follow_camera {
+                camera.position + emitter.position
+            } else {
+                emitter.position
+            };
+
+            let count = (count as usize).min(MAX_PARTICLES.saturating_sub(alive));
+            for _ in 0..count {
+                let offset = glm::vec2(
+                    spread(&mut rng, config.spawn_area.x * 0.5),
+                    spread(&mut rng, config.spawn_area.y * 0.5),
+                );
+                let velocity = config.velocity
+                    + glm::vec2(
+                        spread(&mut rng, config.velocity_spread.x),
+                        spread(&mut rng, config.velocity_spread.y),
+                    );
+
+                galaxy.insert_entity().insert(Particle {
+                    position: origin + offset,
+                    velocity,
+                    rotation: rng.gen::<f32>() * std::f32::consts::TAU,
+                    spin: spread(&mut rng, config.spin),
+                    spawned: now,
+                    state: emitter.state,
+                    config,
+                });
+            }
+            alive += count;
+        }
+
+        if let Some(duration) = config.duration {
+            if now.duration_since(emitter.spawned) > duration {
+                galaxy.remove_entity(e);
+            }
+        }
+    }
+}
+
+pub fn particle_update(galaxy: &Galaxy) {
+    let state = *galaxy
+        .get_resource::<GameState, _>(GameState::single_resource())
+        .unwrap();
+
+    let now = game_now(galaxy);
+
+    for (e, particle) in galaxy.query::<&mut Particle>().eiter() {
+        let life = now.duration_since(particle.spawned).as_secs_f32()
+            / particle.config.lifetime.as_secs_f32();
+        if life > 1.0 {
+            galaxy.remove_entity(e);
+            continue;
+        }
+
+        if particle.state == state {
+            particle.velocity += particle.config.gravity;
+            particle.position += particle.velocity;
+            particle.rotation += particle.spin;
+        }
+
+        if state_on_stack(galaxy, particle.state) {
+            galaxy.insert_event(RendererDrawSprite(particle.as_sprite(life)));
+        }
+    }
+}
+
+pub fn particle_soft_texture() -> TextureData {
+    TextureData::from_fn(32, 32, |x, y| {
+        let falloff = (1.0 - (x * x + y * y).sqrt()).clamp(0.0, 1.0);
+        [255, 255, 255, (falloff * falloff * 255.0) as u8]
+    })
+}
+
+pub fn particle_star_texture() -> TextureData {
+    TextureData::from_fn(32, 32, |x, y| {
+        let angle = y.atan2(x) + std::f32::consts::FRAC_PI_2;
+        let edge = 0.4 + 0.6 * ((angle * 5.0).cos() * 0.5 + 0.5).powi(4);
+        let alpha = if (x * x + y * y).sqrt() < edge {
+            255
+        } else {
+            0
+        };
+        [255, 255, 255, alpha]
+    })
+}
+
+pub fn particle_leaf_texture() -> TextureData {
+    TextureData::from_fn(32, 32, |x, y| {
+        let width = 0.55 * (1.0 - y * y);
+        let alpha = if x.abs() < width { 255 } else { 0 };
+        let vein = if x.abs() < 0.05 { 180 } else { 255 };
+        [vein, vein, vein, alpha]
+    })
+}
@@ -0,0 +1,137 @@
+use super::*;
+
+const BACKDROP_ALPHA: f32 = 0.6;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum PausePage {
+    #[default]
+    Main,
+    ConfirmQuit,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PauseItem {
+    Resume,
+    Settings,
+    Restart,
+    Quit,
+    QuitNo,
+    QuitYes,
+}
+
+impl PausePage {
+    fn title(&self) -> &'static str {
+        match self {
+            PausePage::Main => "PAUSED",
+            PausePage::ConfirmQuit => "QUIT GAME?",
+        }
+    }
+
+    fn items(&self) -> &'static [PauseItem] {
+        match self {
+            PausePage::Main => &[
+                PauseItem::Resume,
+                PauseItem::Settings,
+                PauseItem::Restart,
+                PauseItem::Quit,
+            ],
+            PausePage::ConfirmQuit => &[PauseItem::QuitNo, PauseItem::QuitYes],
+        }
+    }
+
+    fn menu_items(&self) -> Vec<MenuItem> {
+        self.items()
+            .iter()
+            .map(|item| {
+                MenuItem::new(match item {
+                    PauseItem::Resume => "Resume",
+                    PauseItem::Settings => "Settings",
+                    PauseItem::Restart => "Restart",
+                    PauseItem::Quit => "Quit",
+                    PauseItem::QuitNo => "No",
+                    PauseItem::QuitYes => "Yes",
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(SingleResource, Default, Clone, Copy)]
+struct PauseMenu {
+    page: PausePage,
+    selected: usize,
+}
+
+impl PauseMenu {
+    fn open_page(&mut self, page: PausePage) {
+        self.page = page;
+        self.selected = 0;
+    }
+}
+
+pub fn pause_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(PauseMenu::single_resource(), PauseMenu::default());
+}
+
+/// Freeze the current state and overlay the pause menu on `page`. Pausing also saves the game.
+pub fn pause_open(galaxy: &Galaxy, page: PausePage) {
+    save_game(galaxy);
+    galaxy
+        .get_mut_resource::<PauseMenu, _>(PauseMenu::single_resource())
+        .unwrap()
+        .open_page(page);
+    state_push(galaxy, GameState::Pause);
+}
+
+pub fn pause_update(galaxy: &Galaxy) {
+    let keys = key_bindings(galaxy);
+    let mut menu = galaxy
+        .get_mut_resource::<PauseMenu, _>(PauseMenu::single_resource())
+        .unwrap();
+
+    for input in keyboard_inputs(galaxy) {
+        let back =
+            input.virtual_keycode == Some(keys.pause) && input.state == ElementState::Released;
+        let Some(input) = menu_input(&input).or(back.then_some(MenuInput::Back)) else {
+            continue;
+        };
+
+        let item = menu.page.items()[menu.selected];
+        match input {
+            MenuInput::Back => match menu.page {
+                PausePage::Main => state_pop(galaxy),
+                PausePage::ConfirmQuit => menu.open_page(PausePage::Main),
+            },
+            MenuInput::Select => match item {
+                PauseItem::Resume => state_pop(galaxy),
+                PauseItem::Settings => state_push(galaxy, GameState::Settings),
+                PauseItem::Restart => {
+                    new_game(galaxy);
+                }
+                PauseItem::Quit => menu.open_page(PausePage::ConfirmQuit),
+                PauseItem::QuitNo => menu.open_page(PausePage::Main),
+                PauseItem::QuitYes => {
+                    save_game(galaxy);
+                    galaxy.set_exit();
+                }
+            },
+            _ => {
+                let items = menu.page.menu_items();
+                menu_navigate(&mut menu.selected, &items, input);
+            }
+        }
+    }
+}
+
+pub fn pause_render(galaxy: &Galaxy) {
+    let menu = *galaxy
+        .get_resource::<PauseMenu, _>(PauseMenu::single_resource())
+        .unwrap();
+    draw_menu(
+        galaxy,
+        menu.page.title(),
+        &menu.page.menu_items(),
+        menu.selected,
+        BACKDROP_ALPHA,
+    );
+}
@@ -0,0 +1,31 @@
+use super::*;
+
+const REPLAY_POSITION: glm::Vec2 = glm::Vec2::new(0.0, 0.92);
+const REPLAY_SIZE: f32 = 0.05;
+const REPLAY_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 0.3, 0.3, 1.0);
+
+/// Marks the screen while a replay is playing, with how far along it is.
+pub fn replay_render_update(galaxy: &Galaxy) {
+    let status = *galaxy
+        .get_resource::<ReplayStatus, _>(ReplayStatus::single_resource())
+        .unwrap();
+    if !status.playing {
+        return;
+    }
+
+    let camera = *galaxy
+        .get_resource::<RendererCamera, _>(RendererCamera::single_resource())
+        .unwrap();
+    draw_text(
+        galaxy,
+        &format!(
+            "REPLAY {}/{}{}",
+            status.tick,
+            status.ticks,
+            if status.fast_forward { " >>" } else { "" }
+        ),
+        camera.position + REPLAY_POSITION,
+        REPLAY_SIZE,
+        REPLAY_COLOR,
+    );
+}
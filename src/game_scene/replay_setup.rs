@@ -0,0 +1,68 @@
+use super::*;
+use settings::Settings;
+
+/// Everything besides input that decides how a session plays out, kept with the replay.
+#[derive(Serialize, Deserialize)]
+struct GameSetup {
+    settings: Settings,
+    rules: GameRules,
+    tuning: Tuning,
+}
+
+/// Start from the setup a replay was recorded with, or have it recorded.
+pub fn replay_setup_init(galaxy: &Galaxy) {
+    if galaxy
+        .get_resource::<ReplayStatus, _>(ReplayStatus::single_resource())
+        .unwrap()
+        .playing
+    {
+        replay_setup_update(galaxy);
+    } else {
+        replay_setup_record(galaxy);
+    }
+}
+
+/// Have the current setup recorded, for when it changes mid-session.
+pub fn replay_setup_record(galaxy: &Galaxy) {
+    let setup = GameSetup {
+        settings: settings::settings(galaxy),
+        rules: rules(galaxy),
+        tuning: tuning(galaxy),
+    };
+    galaxy
+        .get_mut_resource::<ReplaySetup, _>(ReplaySetup::single_resource())
+        .unwrap()
+        .record = Some(ron::to_string(&setup).unwrap());
+}
+
+/// Switch to the recorded setup once playback reaches it.
+pub fn replay_setup_update(galaxy: &Galaxy) {
+    let Some(data) = galaxy
+        .get_mut_resource::<ReplaySetup, _>(ReplaySetup::single_resource())
+        .unwrap()
+        .play
+        .take()
+    else {
+        return;
+    };
+    let setup = match ron::from_str::<GameSetup>(&data) {
+        Ok(setup) => setup,
+        Err(e) => {
+            eprintln!("Ignoring the replay's setup: {}", e);
+            return;
+        }
+    };
+
+    // Volume and window mode are left to whoever is watching.
+    let mut settings = galaxy
+        .get_mut_resource::<Settings, _>(Settings::single_resource())
+        .unwrap();
+    settings.difficulty = setup.settings.difficulty;
+    settings.custom_rules = setup.settings.custom_rules;
+    settings.keys = setup.settings.keys;
+    drop(settings);
+    **galaxy
+        .get_mut_resource::<Tuning, _>(Tuning::single_resource())
+        .unwrap() = setup.tuning;
+    rules_restore(galaxy, setup.rules);
+}
@@ -0,0 +1,91 @@
+use super::*;
+
+/// Separate streams keep one system's draws from shifting another's, so adding a particle
+/// effect doesn't change where the candy lands.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RngStream {
+    Map,
+    Npc,
+    Candy,
+    Candyland,
+    Hud,
+    Particles,
+}
+
+const STREAM_COUNT: usize = 6;
+
+/// All gameplay randomness for a run, derived from a single seed.
+/// Each draw hands out a generator seeded from the run seed, the stream and a counter,
+/// so the whole state is a handful of numbers that go into save games as is.
+#[derive(SingleResource, Clone, Copy, Serialize, Deserialize)]
+pub struct GameRng {
+    seed: u64,
+    /// How many runs this session has started, for picking the next seed.
+    run: u64,
+    draws: [u64; STREAM_COUNT],
+}
+
+impl GameRng {
+    fn new(galaxy: &Galaxy, run: u64) -> Self {
+        let session = *galaxy
+            .get_resource::<SessionSeed, _>(SessionSeed::single_resource())
+            .unwrap();
+        let seed = if session.pinned {
+            session.seed
+        } else {
+            StdRng::seed_from_u64(session.seed ^ run.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+                .gen::<u32>() as u64
+        };
+
+        Self {
+            seed,
+            run,
+            draws: [0; STREAM_COUNT],
+        }
+    }
+
+    fn next(&mut self, stream: RngStream) -> StdRng {
+        let i = stream as usize;
+        let draw = self.draws[i];
+        self.draws[i] += 1;
+        StdRng::seed_from_u64(
+            self.seed.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+                ^ (i as u64 + 1).wrapping_mul(0xbf58_476d_1ce4_e5b9)
+                ^ draw.wrapping_mul(0x94d0_49bb_1331_11eb),
+        )
+    }
+}
+
+pub fn rng_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(GameRng::single_resource(), GameRng::new(galaxy, 0));
+}
+
+/// Pick the seed for a new run. Seeds follow from the session seed, so replays get the same ones.
+pub fn rng_reset(galaxy: &Galaxy) {
+    let run = rng_snapshot(galaxy).run + 1;
+    rng_restore(galaxy, GameRng::new(galaxy, run));
+}
+
+pub fn rng_restore(galaxy: &Galaxy, rng: GameRng) {
+    **galaxy
+        .get_mut_resource::<GameRng, _>(GameRng::single_resource())
+        .unwrap() = rng;
+}
+
+pub fn rng_snapshot(galaxy: &Galaxy) -> GameRng {
+    *galaxy
+        .get_resource::<GameRng, _>(GameRng::single_resource())
+        .unwrap()
+}
+
+pub fn rng_seed(galaxy: &Galaxy) -> u64 {
+    rng_snapshot(galaxy).seed
+}
+
+/// A generator for the next draw from `stream`. Take one per update rather than per value.
+pub fn game_rng(galaxy: &Galaxy, stream: RngStream) -> StdRng {
+    galaxy
+        .get_mut_resource::<GameRng, _>(GameRng::single_resource())
+        .unwrap()
+        .next(stream)
+}
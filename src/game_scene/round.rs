@@ -0,0 +1,137 @@
+use super::*;
+
+const DUSK_HOUR: f32 = 18.0;
+const NIGHT_HOURS: f32 = 6.0;
+/// Fraction of the night left when the clock starts warning the player.
+const LATE_FRACTION: f32 = 0.1;
+const CLOCK_X: f32 = 0.65;
+const CLOCK_Y: f32 = 0.82;
+const CLOCK_SIZE: f32 = 0.08;
+const CLOCK_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 0.8, 0.5, 1.0);
+const CLOCK_LATE_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 0.25, 0.2, 1.0);
+const STAR_SPACING: f32 = 0.2;
+const STAR_SIZE: f32 = 0.2;
+const STAR_COLOR: glm::Vec4 = glm::Vec4::new(1.0, 0.85, 0.2, 1.0);
+const STAR_EMPTY_COLOR: glm::Vec4 = glm::Vec4::new(0.25, 0.25, 0.25, 1.0);
+const MAX_STARS: usize = 3;
+
+/// How the last round ended, for the outro and game over screens.
+#[derive(SingleResource, Default, Clone, Copy)]
+pub struct RoundResult {
+    pub score: usize,
+    pub stars: usize,
+}
+
+pub fn round_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(RoundResult::single_resource(), RoundResult::default());
+}
+
+/// One star for making the target, another at one and a half times it, and the last for doubling it.
+fn stars(score: usize, target: usize) -> usize {
+    if score >= target * 2 {
+        3
+    } else if score * 2 >= target * 3 {
+        2
+    } else if score >= target {
+        1
+    } else {
+        0
+    }
+}
+
+fn round_progress(galaxy: &Galaxy) -> f32 {
+    let elapsed = game_now(galaxy).duration_since(stats(galaxy).started);
+    (elapsed.as_secs_f32() / rules(galaxy).round_duration.as_secs_f32()).min(1.0)
+}
+
+fn clock_text(progress: f32) -> String {
+    let minutes = ((DUSK_HOUR + progress * NIGHT_HOURS) * 60.0) as usize;
+    let (hour, minute) = (minutes / 60 % 24, minutes % 60);
+    let suffix = if hour < 12 { "AM" } else { "PM" };
+    let hour = match hour % 12 {
+        0 => 12,
+        hour => hour,
+    };
+    format!("{}:{:02} {}", hour, minute, suffix)
+}
+
+/// End the round once the clock strikes midnight, whether or not the target was made.
+pub fn round_update(galaxy: &Galaxy) {
+    let state = *galaxy
+        .get_resource::<GameState, _>(GameState::single_resource())
+        .unwrap();
+    if !matches!(state, GameState::Map | GameState::CandyLand) || round_progress(galaxy) < 1.0 {
+        return;
+    }
+
+    let score = galaxy
+        .get_resource::<Player, _>(Player::single_resource())
+        .unwrap()
+        .score;
+    let target = rules(galaxy).win_score;
+    let won = score >= target;
+    let next = if won {
+        GameState::Outro
+    } else {
+        GameState::GameOver
+    };
+
+    if transition_to(galaxy, TransitionKind::Fade, next) {
+        **galaxy
+            .get_mut_resource::<RoundResult, _>(RoundResult::single_resource())
+            .unwrap() = RoundResult {
+            score,
+            stars: stars(score, target),
+        };
+        save_clear();
+        high_scores_record(galaxy);
+        if won {
+            audio_success(galaxy);
+        } else {
+            audio_no(galaxy);
+        }
+    }
+}
+
+pub fn round_render_update(galaxy: &Galaxy) {
+    let state = *galaxy
+        .get_resource::<GameState, _>(GameState::single_resource())
+        .unwrap();
+    if !state_on_stack(galaxy, GameState::Map) && !state_on_stack(galaxy, GameState::CandyLand) {
+        return;
+    }
+
+    let progress = round_progress(galaxy);
+    draw_text(
+        galaxy,
+        &clock_text(progress),
+        glm::vec2(CLOCK_X, CLOCK_Y) + hud_offset(galaxy, state),
+        CLOCK_SIZE,
+        if progress > 1.0 - LATE_FRACTION {
+            CLOCK_LATE_COLOR
+        } else {
+            CLOCK_COLOR
+        },
+    );
+}
+
+/// Draws the last round's stars in a row centered on `position`.
+pub fn round_draw_stars(galaxy: &Galaxy, position: glm::Vec2) {
+    let result = *galaxy
+        .get_resource::<RoundResult, _>(RoundResult::single_resource())
+        .unwrap();
+    for i in 0..MAX_STARS {
+        let offset = (i as f32 - (MAX_STARS - 1) as f32 * 0.5) * STAR_SPACING;
+        draw_text(
+            galaxy,
+            "*",
+            position + glm::vec2(offset, 0.0),
+            STAR_SIZE,
+            if i < result.stars {
+                STAR_COLOR
+            } else {
+                STAR_EMPTY_COLOR
+            },
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use super::*;
+
+/// Everything that decides how hard a night is. Picked from the difficulty setting when a run
+/// starts and kept with the run, so changing difficulty mid-game only affects the next one.
+#[derive(SingleResource, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameRules {
+    /// Candy needed by midnight.
+    pub win_score: usize,
+    pub round_duration: Duration,
+    /// Chance of the granny noticing each piece of candy taken.
+    pub anger_chance: f64,
+    /// Taking this much candy from one house always gets noticed.
+    pub steal_max: usize,
+    pub house_cooldown: Duration,
+    pub kid_count: usize,
+    /// How long a punched kid stays out of reach.
+    pub beating_cooldown: Duration,
+    pub player_acceleration: f32,
+    pub player_friction: f32,
+    pub player_max_speed: f32,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self::preset(Difficulty::Normal)
+    }
+}
+
+impl GameRules {
+    /// The built in rules for `difficulty`, for whatever tuning.ron leaves out.
+    pub fn preset(difficulty: Difficulty) -> Self {
+        let normal = Self {
+            win_score: 100,
+            round_duration: Duration::from_secs(5 * 60),
+            anger_chance: 0.2,
+            steal_max: 15,
+            house_cooldown: Duration::from_secs(30),
+            kid_count: 12,
+            beating_cooldown: Duration::from_secs(12),
+            player_acceleration: 0.0006,
+            player_friction: 0.001,
+            player_max_speed: 0.02,
+        };
+
+        match difficulty {
+            Difficulty::Easy => Self {
+                win_score: 75,
+                round_duration: Duration::from_secs(6 * 60),
+                anger_chance: 0.1,
+                steal_max: 20,
+                house_cooldown: Duration::from_secs(20),
+                kid_count: 16,
+                beating_cooldown: Duration::from_secs(8),
+                player_acceleration: 0.0007,
+                player_max_speed: 0.022,
+                ..normal
+            },
+            Difficulty::Normal | Difficulty::Custom => normal,
+            Difficulty::Hard => Self {
+                win_score: 125,
+                round_duration: Duration::from_secs(4 * 60),
+                anger_chance: 0.3,
+                steal_max: 10,
+                house_cooldown: Duration::from_secs(45),
+                kid_count: 8,
+                beating_cooldown: Duration::from_secs(18),
+                player_max_speed: 0.018,
+                ..normal
+            },
+        }
+    }
+}
+
+/// A rule that can be tweaked from the custom rules page.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RuleField {
+    WinScore,
+    RoundDuration,
+    AngerChance,
+    StealMax,
+    HouseCooldown,
+    KidCount,
+    BeatingCooldown,
+    PlayerSpeed,
+}
+
+impl RuleField {
+    pub const ALL: [RuleField; 8] = [
+        RuleField::WinScore,
+        RuleField::RoundDuration,
+        RuleField::AngerChance,
+        RuleField::StealMax,
+        RuleField::HouseCooldown,
+        RuleField::KidCount,
+        RuleField::BeatingCooldown,
+        RuleField::PlayerSpeed,
+    ];
+
+    /// Speed is shown relative to `normal`.
+    pub fn label(&self, rules: &GameRules, normal: &GameRules) -> String {
+        match self {
+            RuleField::WinScore => format!("Target < {} >", rules.win_score),
+            RuleField::RoundDuration => format!(
+                "Night Length < {} min >",
+                rules.round_duration.as_secs() / 60
+            ),
+            RuleField::AngerChance => {
+                format!("Granny Anger < {}% >", (rules.anger_chance * 100.0).round())
+            }
+            RuleField::StealMax => format!("Steal Limit < {} >", rules.steal_max),
+            RuleField::HouseCooldown => {
+                format!("House Cooldown < {}s >", rules.house_cooldown.as_secs())
+            }
+            RuleField::KidCount => format!("Kids < {} >", rules.kid_count),
+            RuleField::BeatingCooldown => {
+                format!("Punch Cooldown < {}s >", rules.beating_cooldown.as_secs())
+            }
+            RuleField::PlayerSpeed => format!(
+                "Speed < {}% >",
+                (rules.player_max_speed / normal.player_max_speed * 100.0).round()
+            ),
+        }
+    }
+
+    /// Nudge this rule up or down a notch, within sensible limits. Speed steps in tenths of
+    /// `normal`.
+    pub fn step(&self, rules: &mut GameRules, normal: &GameRules, forward: bool) {
+        fn step_usize(value: usize, step: usize, min: usize, max: usize, forward: bool) -> usize {
+            if forward {
+                (value + step).min(max)
+            } else {
+                value.saturating_sub(step).max(min)
+            }
+        }
+        fn step_secs(value: Duration, step: u64, min: u64, max: u64, forward: bool) -> Duration {
+            Duration::from_secs(step_usize(
+                value.as_secs() as usize,
+                step as usize,
+                min as usize,
+                max as usize,
+                forward,
+            ) as u64)
+        }
+
+        match self {
+            RuleField::WinScore => {
+                rules.win_score = step_usize(rules.win_score, 25, 25, 500, forward);
+            }
+            RuleField::RoundDuration => {
+                rules.round_duration = step_secs(rules.round_duration, 60, 60, 20 * 60, forward);
+            }
+            RuleField::AngerChance => {
+                let step = if forward { 0.05 } else { -0.05 };
+                rules.anger_chance = ((rules.anger_chance + step) * 20.0)
+                    .round()
+                    .clamp(0.0, 20.0)
+                    / 20.0;
+            }
+            RuleField::StealMax => {
+                rules.steal_max = step_usize(rules.steal_max, 1, 5, 50, forward);
+            }
+            RuleField::HouseCooldown => {
+                rules.house_cooldown = step_secs(rules.house_cooldown, 5, 0, 120, forward);
+            }
+            RuleField::KidCount => {
+                rules.kid_count = step_usize(rules.kid_count, 1, 0, 40, forward);
+            }
+            RuleField::BeatingCooldown => {
+                rules.beating_cooldown = step_secs(rules.beating_cooldown, 1, 1, 60, forward);
+            }
+            RuleField::PlayerSpeed => {
+                // Speed and acceleration scale together so the player handles the same.
+                let percent = (rules.player_max_speed / normal.player_max_speed * 10.0).round()
+                    + if forward { 1.0 } else { -1.0 };
+                let scale = percent.clamp(5.0, 20.0) / 10.0;
+                rules.player_max_speed = normal.player_max_speed * scale;
+                rules.player_acceleration = normal.player_acceleration * scale;
+            }
+        }
+    }
+}
+
+pub fn rules_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(GameRules::single_resource(), settings_rules(galaxy));
+}
+
+/// Use the rules from the current difficulty setting for the next run.
+pub fn rules_reset(galaxy: &Galaxy) {
+    rules_restore(galaxy, settings_rules(galaxy));
+}
+
+pub fn rules_restore(galaxy: &Galaxy, rules: GameRules) {
+    **galaxy
+        .get_mut_resource::<GameRules, _>(GameRules::single_resource())
+        .unwrap() = rules;
+}
+
+pub fn rules(galaxy: &Galaxy) -> GameRules {
+    *galaxy
+        .get_resource::<GameRules, _>(GameRules::single_resource())
+        .unwrap()
+}
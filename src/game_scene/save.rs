@@ -0,0 +1,84 @@
+use super::*;
+
+const SAVE_FILE: &str = "save.ron";
+/// Bump whenever the snapshot layout changes. Saves from other versions are ignored.
+const SAVE_VERSION: u32 = 11;
+
+#[derive(Serialize, Deserialize)]
+struct SaveGame {
+    version: u32,
+    /// Timers are stored as game time, so the clock comes along to keep them meaningful.
+    clock: GameInstant,
+    state: GameState,
+    rules: GameRules,
+    rng: GameRng,
+    map: map::MapSnapshot,
+    candyland: candyland::Candyland,
+    stats: RunStats,
+    notoriety: Notoriety,
+}
+
+fn save_read() -> Option<SaveGame> {
+    let save = ron::from_str::<SaveGame>(&storage_read(SAVE_FILE)?).ok()?;
+    (save.version == SAVE_VERSION).then_some(save)
+}
+
+pub fn save_exists() -> bool {
+    save_read().is_some()
+}
+
+/// Snapshot the current run. Only gameplay states are saved, so this does nothing elsewhere.
+pub fn save_game(galaxy: &Galaxy) {
+    let state = galaxy
+        .get_resource::<GameStateMachine, _>(GameStateMachine::single_resource())
+        .unwrap()
+        .gameplay_state();
+    let Some(state) = state else {
+        return;
+    };
+
+    let save = SaveGame {
+        version: SAVE_VERSION,
+        clock: game_now(galaxy),
+        state,
+        rules: rules(galaxy),
+        rng: rng_snapshot(galaxy),
+        map: map::map_snapshot(galaxy),
+        candyland: candyland::candyland_snapshot(galaxy),
+        stats: stats(galaxy),
+        notoriety: notoriety(galaxy),
+    };
+    storage_write(
+        SAVE_FILE,
+        &ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default()).unwrap(),
+    );
+}
+
+/// A finished run can't be continued.
+pub fn save_clear() {
+    storage_remove(SAVE_FILE);
+}
+
+/// Fade into the saved run. Returns `false` if there's nothing to continue or a transition is running.
+pub fn continue_game(galaxy: &Galaxy) -> bool {
+    let Some(save) = save_read() else {
+        return false;
+    };
+    transition_to_then(galaxy, TransitionKind::Fade, save.state, continue_game_load)
+}
+
+fn continue_game_load(galaxy: &Galaxy) {
+    let Some(save) = save_read() else {
+        return;
+    };
+
+    score::score_reset(galaxy);
+    particle::particle_reset(galaxy);
+    game_clock_set(galaxy, save.clock);
+    rules_restore(galaxy, save.rules);
+    rng_restore(galaxy, save.rng);
+    map::map_restore(galaxy, save.map);
+    candyland::candyland_restore(galaxy, save.candyland);
+    stats_restore(galaxy, save.stats);
+    notoriety_restore(galaxy, save.notoriety);
+}
@@ -4,7 +4,8 @@ const TOP_X: f32 = -0.8;
 const TOP_Y: f32 = 0.8;
 const OFFSET: f32 = 0.2;
 const SCORE_INCREMENT_Y_INCREMENT: f32 = 0.02;
-const WIN_SCORE: usize = 100;
+const SCORE_INCREMENT_START_Y: f32 = 0.8;
+const SCORE_INCREMENT_END_Y: f32 = 1.5;
 
 #[derive(CheapComponent, Clone, Copy)]
 pub struct ScorePop {
@@ -12,16 +13,55 @@ pub struct ScorePop {
     is_positive: bool,
 }
 
+impl ScorePop {
+    fn alpha(&self) -> f32 {
+        let travelled = if self.is_positive {
+            self.position.y + SCORE_INCREMENT_START_Y
+        } else {
+            SCORE_INCREMENT_START_Y - self.position.y
+        };
+        (1.0 - travelled / (SCORE_INCREMENT_START_Y + SCORE_INCREMENT_END_Y)).clamp(0.0, 1.0)
+    }
+}
+
 fn spawn(galaxy: &Galaxy, is_positive: bool) {
-    let mut rng = rand::thread_rng();
+    let mut rng = game_rng(galaxy, RngStream::Hud);
     let x_rand = (rng.gen::<f32>() - 0.5) * 1.5;
 
     galaxy.insert_entity().insert(ScorePop {
         is_positive,
-        position: glm::vec2(x_rand, if is_positive { -0.8 } else { 0.8 }),
+        position: glm::vec2(
+            x_rand,
+            if is_positive {
+                -SCORE_INCREMENT_START_Y
+            } else {
+                SCORE_INCREMENT_START_Y
+            },
+        ),
     });
 }
 
+/// HUD positions are relative to the camera, and pushed off screen where there's no HUD.
+pub fn hud_offset(galaxy: &Galaxy, state: GameState) -> glm::Vec2 {
+    match state {
+        GameState::Intro | GameState::Outro | GameState::GameOver | GameState::Editor => {
+            glm::vec2(100.0, 100.0)
+        }
+        _ => {
+            galaxy
+                .get_resource::<RendererCamera, _>(RendererCamera::single_resource())
+                .unwrap()
+                .position
+        }
+    }
+}
+
+pub fn score_reset(galaxy: &Galaxy) {
+    for (e, _) in galaxy.query::<&ScorePop>().eiter() {
+        galaxy.remove_entity(e);
+    }
+}
+
 pub fn spawn_score_increment(galaxy: &Galaxy) {
     spawn(galaxy, true);
 }
@@ -47,38 +87,38 @@ pub fn score_render_update(galaxy: &Galaxy) {
         n /= 10;
     }
 
-    let delta = match *state {
-        GameState::Map => player.position,
-        GameState::Intro | GameState::Outro => glm::vec2(100.0, 100.0),
-        _ => glm::vec2(0.0, 0.0),
-    };
+    let delta = hud_offset(galaxy, *state);
 
     let mut last_i = 0;
     for (i, digit) in digits.iter().rev().enumerate() {
         galaxy.insert_event(RendererDrawSprite(Sprite {
-            visible: 1.0,
             texture_index: SPRITE_S0 + digit,
-            color: glm::vec3(1.0, 1.0, 1.0),
+            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
             rotation: 0.0,
             scale: glm::vec2(0.2, 0.2),
             position: glm::vec2(TOP_X + i as f32 * OFFSET, TOP_Y) + delta,
+            unlit: true,
+            ..Default::default()
         }));
         last_i = i;
     }
     galaxy.insert_event(RendererDrawSprite(Sprite {
-        visible: 1.0,
         texture_index: SPRITE_CANDY,
-        color: glm::vec3(1.0, 1.0, 1.0),
+        color: glm::vec4(1.0, 1.0, 1.0, 1.0),
         rotation: 0.0,
         scale: glm::vec2(0.2, 0.2),
         position: glm::vec2(TOP_X + (last_i + 1) as f32 * OFFSET, TOP_Y) + delta,
+        unlit: true,
+        ..Default::default()
     }));
 
-    if player.score >= WIN_SCORE && *state == GameState::Map {
-        drop(state);
-        **galaxy
-            .get_mut_resource::<GameState, _>(GameState::single_resource())
-            .unwrap() = GameState::Outro;
+    // Making the target no longer ends the night, the rest of it is spent going for stars.
+    if player.score >= rules(galaxy).win_score
+        && matches!(*state, GameState::Map | GameState::CandyLand)
+        && stats(galaxy).reached_target.is_none()
+    {
+        let now = game_now(galaxy);
+        stats_record(galaxy, |stats| stats.reached_target = Some(now));
         audio_success(galaxy);
     }
 }
@@ -88,46 +128,45 @@ pub fn score_increments_update(galaxy: &Galaxy) {
         .get_resource::<GameState, _>(GameState::single_resource())
         .unwrap();
 
+    // Frozen along with the clock, which Settings opened from the pause menu doesn't restart.
+    let paused =
+        state_on_stack(galaxy, GameState::Pause) || state_on_stack(galaxy, GameState::Editor);
+
     for (e, score_inc) in galaxy.query::<&mut ScorePop>().eiter() {
-        score_inc.position.y += if score_inc.is_positive {
-            SCORE_INCREMENT_Y_INCREMENT
-        } else {
-            -SCORE_INCREMENT_Y_INCREMENT
-        };
-        if score_inc.position.y > 1.5 || score_inc.position.y < -1.5 {
+        if !paused {
+            score_inc.position.y += if score_inc.is_positive {
+                SCORE_INCREMENT_Y_INCREMENT
+            } else {
+                -SCORE_INCREMENT_Y_INCREMENT
+            };
+        }
+        if score_inc.position.y.abs() > SCORE_INCREMENT_END_Y {
             galaxy.remove_entity(e);
         }
 
-        let delta = match *state {
-            GameState::Map => {
-                galaxy
-                    .get_resource::<Player, _>(Player::single_resource())
-                    .unwrap()
-                    .position
-            }
-            GameState::Intro | GameState::Outro => glm::vec2(100.0, 100.0),
-            _ => glm::vec2(0.0, 0.0),
-        };
+        let delta = hud_offset(galaxy, *state);
 
         galaxy.insert_event(RendererDrawSprite(Sprite {
-            visible: 1.0,
             texture_index: if score_inc.is_positive {
                 SPRITE_SPLUS
             } else {
                 SPRITE_SMINUS
             },
-            color: glm::vec3(1.0, 1.0, 1.0),
+            color: glm::vec4(1.0, 1.0, 1.0, score_inc.alpha()),
             rotation: 0.0,
             scale: glm::vec2(0.2, 0.2),
             position: glm::vec2(score_inc.position.x, score_inc.position.y) + delta,
+            unlit: true,
+            ..Default::default()
         }));
         galaxy.insert_event(RendererDrawSprite(Sprite {
-            visible: 1.0,
             texture_index: SPRITE_CANDY,
-            color: glm::vec3(1.0, 1.0, 1.0),
+            color: glm::vec4(1.0, 1.0, 1.0, score_inc.alpha()),
             rotation: 0.0,
             scale: glm::vec2(0.2, 0.2),
             position: glm::vec2(score_inc.position.x + 0.2, score_inc.position.y) + delta,
+            unlit: true,
+            ..Default::default()
         }));
     }
 }
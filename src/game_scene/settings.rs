@@ -0,0 +1,541 @@
+use super::*;
+
+const SETTINGS_FILE: &str = "settings.ron";
+const VOLUME_STEP: f64 = 0.1;
+const BACKDROP_ALPHA: f32 = 0.85;
+const STATUS_Y: f32 = -0.85;
+const STATUS_SIZE: f32 = 0.05;
+const STATUS_COLOR: glm::Vec4 = glm::Vec4::new(0.9, 0.9, 0.9, 1.0);
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    /// The rules from the custom rules page.
+    Custom,
+}
+
+impl Difficulty {
+    const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Custom,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Custom => "Custom",
+        }
+    }
+
+    fn cycle(&self, forward: bool) -> Self {
+        let i = Difficulty::ALL.iter().position(|d| d == self).unwrap();
+        let step = if forward {
+            1
+        } else {
+            Difficulty::ALL.len() - 1
+        };
+        Difficulty::ALL[(i + step) % Difficulty::ALL.len()]
+    }
+}
+
+/// Every action has its own key. Escape always backs out, pausing on the map and leaving
+/// houses, and Q always quits, so neither can be bound.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub up: VirtualKeyCode,
+    pub down: VirtualKeyCode,
+    pub left: VirtualKeyCode,
+    pub right: VirtualKeyCode,
+    /// Knock on doors and take candy.
+    pub interact: VirtualKeyCode,
+    pub punch: VirtualKeyCode,
+    /// Walk out of a house, as well as Escape.
+    pub leave: VirtualKeyCode,
+    pub pause: VirtualKeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            up: VirtualKeyCode::W,
+            down: VirtualKeyCode::S,
+            left: VirtualKeyCode::A,
+            right: VirtualKeyCode::D,
+            interact: VirtualKeyCode::E,
+            punch: VirtualKeyCode::F,
+            leave: VirtualKeyCode::X,
+            pause: VirtualKeyCode::P,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum KeyAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Interact,
+    Punch,
+    Leave,
+    Pause,
+}
+
+impl KeyAction {
+    const ALL: [KeyAction; 8] = [
+        KeyAction::Up,
+        KeyAction::Down,
+        KeyAction::Left,
+        KeyAction::Right,
+        KeyAction::Interact,
+        KeyAction::Punch,
+        KeyAction::Leave,
+        KeyAction::Pause,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            KeyAction::Up => "Up",
+            KeyAction::Down => "Down",
+            KeyAction::Left => "Left",
+            KeyAction::Right => "Right",
+            KeyAction::Interact => "Interact",
+            KeyAction::Punch => "Punch",
+            KeyAction::Leave => "Leave",
+            KeyAction::Pause => "Pause",
+        }
+    }
+
+    fn key(&self, mut keys: KeyBindings) -> VirtualKeyCode {
+        *self.key_mut(&mut keys)
+    }
+
+    /// Bind `key` to this action, swapping with whichever action had it. Returns that action.
+    fn bind(&self, keys: &mut KeyBindings, key: VirtualKeyCode) -> Option<KeyAction> {
+        let old = self.key(*keys);
+        let other = KeyAction::ALL
+            .into_iter()
+            .find(|other| other != self && other.key(*keys) == key);
+        if let Some(other) = other {
+            *other.key_mut(keys) = old;
+        }
+        *self.key_mut(keys) = key;
+        other
+    }
+
+    fn key_mut<'a>(&self, keys: &'a mut KeyBindings) -> &'a mut VirtualKeyCode {
+        match self {
+            KeyAction::Up => &mut keys.up,
+            KeyAction::Down => &mut keys.down,
+            KeyAction::Left => &mut keys.left,
+            KeyAction::Right => &mut keys.right,
+            KeyAction::Interact => &mut keys.interact,
+            KeyAction::Punch => &mut keys.punch,
+            KeyAction::Leave => &mut keys.leave,
+            KeyAction::Pause => &mut keys.pause,
+        }
+    }
+}
+
+#[derive(SingleResource, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f64,
+    pub effects_volume: f64,
+    pub window_mode: WindowMode,
+    pub difficulty: Difficulty,
+    /// Left as `None` until changed, which plays by Normal.
+    pub custom_rules: Option<GameRules>,
+    pub keys: KeyBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            effects_volume: 1.0,
+            window_mode: WindowMode::default(),
+            difficulty: Difficulty::default(),
+            custom_rules: None,
+            keys: KeyBindings::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SettingsPage {
+    Main,
+    Keys,
+    Rules,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SettingsItem {
+    MasterVolume,
+    EffectsVolume,
+    WindowMode,
+    Difficulty,
+    CustomRules,
+    Keys,
+    Back,
+}
+
+const SETTINGS_ITEMS: [SettingsItem; 7] = [
+    SettingsItem::MasterVolume,
+    SettingsItem::EffectsVolume,
+    SettingsItem::WindowMode,
+    SettingsItem::Difficulty,
+    SettingsItem::CustomRules,
+    SettingsItem::Keys,
+    SettingsItem::Back,
+];
+
+/// Keys the game keeps for itself.
+fn key_reserved(key: VirtualKeyCode) -> bool {
+    let reserved = matches!(key, VirtualKeyCode::Escape | VirtualKeyCode::Q);
+    #[cfg(debug_assertions)]
+    let reserved = reserved || map::DEBUG_KEYS.contains(&key);
+    reserved
+}
+
+/// What became of the last key pressed for a binding.
+#[derive(Clone, Copy)]
+enum KeyStatus {
+    Reserved(VirtualKeyCode),
+    /// This action got the key that was replaced.
+    Swapped(KeyAction, VirtualKeyCode),
+}
+
+#[derive(SingleResource, Clone, Copy)]
+struct SettingsMenu {
+    page: SettingsPage,
+    selected: usize,
+    /// Waiting for the next key press to bind to this action.
+    rebinding: Option<KeyAction>,
+    status: Option<KeyStatus>,
+}
+
+impl SettingsMenu {
+    fn open_page(&mut self, page: SettingsPage) {
+        self.page = page;
+        self.selected = 0;
+        self.rebinding = None;
+        self.status = None;
+    }
+
+    fn open_main_page_at(&mut self, item: SettingsItem) {
+        self.open_page(SettingsPage::Main);
+        self.selected = SETTINGS_ITEMS.iter().position(|&i| i == item).unwrap();
+    }
+
+    fn items(&self, settings: &Settings, normal: &GameRules) -> Vec<MenuItem> {
+        match self.page {
+            SettingsPage::Main => SETTINGS_ITEMS
+                .iter()
+                .map(|item| {
+                    let label = match item {
+                        SettingsItem::MasterVolume => {
+                            format!("Volume < {}% >", (settings.master_volume * 100.0).round())
+                        }
+                        SettingsItem::EffectsVolume => {
+                            format!("Effects < {}% >", (settings.effects_volume * 100.0).round())
+                        }
+                        SettingsItem::WindowMode => match settings.window_mode {
+                            WindowMode::Windowed => String::from("Window < Windowed >"),
+                            WindowMode::Fullscreen => String::from("Window < Fullscreen >"),
+                        },
+                        SettingsItem::Difficulty => {
+                            format!("Difficulty < {} >", settings.difficulty.name())
+                        }
+                        SettingsItem::CustomRules => String::from("Custom Rules"),
+                        SettingsItem::Keys => String::from("Key Bindings"),
+                        SettingsItem::Back => String::from("Back"),
+                    };
+                    MenuItem::new(label).enabled(
+                        *item != SettingsItem::CustomRules
+                            || settings.difficulty == Difficulty::Custom,
+                    )
+                })
+                .collect(),
+            SettingsPage::Keys => KeyAction::ALL
+                .iter()
+                .map(|action| {
+                    if self.rebinding == Some(*action) {
+                        MenuItem::new(format!("{}: ...", action.name()))
+                    } else {
+                        MenuItem::new(format!(
+                            "{}: {:?}",
+                            action.name(),
+                            action.key(settings.keys)
+                        ))
+                    }
+                })
+                .chain([MenuItem::new("Reset"), MenuItem::new("Back")])
+                .collect(),
+            SettingsPage::Rules => RuleField::ALL
+                .iter()
+                .map(|field| {
+                    MenuItem::new(field.label(&settings.custom_rules.unwrap_or(*normal), normal))
+                })
+                .chain([MenuItem::new("Reset"), MenuItem::new("Back")])
+                .collect(),
+        }
+    }
+}
+
+pub fn settings_init(galaxy: &Galaxy) {
+    let settings = storage_read(SETTINGS_FILE)
+        .and_then(|data| ron::from_str::<Settings>(&data).ok())
+        .unwrap_or_default();
+
+    galaxy.insert_resource(Settings::single_resource(), settings);
+    galaxy.insert_resource(
+        SettingsMenu::single_resource(),
+        SettingsMenu {
+            page: SettingsPage::Main,
+            selected: 0,
+            rebinding: None,
+            status: None,
+        },
+    );
+    settings_apply(galaxy);
+}
+
+pub fn settings(galaxy: &Galaxy) -> Settings {
+    *galaxy
+        .get_resource::<Settings, _>(Settings::single_resource())
+        .unwrap()
+}
+
+pub fn key_bindings(galaxy: &Galaxy) -> KeyBindings {
+    settings(galaxy).keys
+}
+
+/// The rules the difficulty setting asks for.
+pub fn settings_rules(galaxy: &Galaxy) -> GameRules {
+    let settings = settings(galaxy);
+    match settings.difficulty {
+        Difficulty::Custom => settings
+            .custom_rules
+            .unwrap_or(tuning(galaxy).difficulty.normal),
+        difficulty => tuning(galaxy).difficulty.preset(difficulty),
+    }
+}
+
+fn settings_apply(galaxy: &Galaxy) {
+    let settings = settings(galaxy);
+    audio_set_volume(galaxy, settings.master_volume, settings.effects_volume);
+    **galaxy
+        .get_mut_resource::<WindowMode, _>(WindowMode::single_resource())
+        .unwrap() = settings.window_mode;
+}
+
+pub fn settings_enter(galaxy: &Galaxy) {
+    galaxy
+        .get_mut_resource::<SettingsMenu, _>(SettingsMenu::single_resource())
+        .unwrap()
+        .open_page(SettingsPage::Main);
+}
+
+pub fn settings_exit(galaxy: &Galaxy) {
+    // A replay's settings are the recording's, not this machine's.
+    if galaxy
+        .get_resource::<ReplayStatus, _>(ReplayStatus::single_resource())
+        .unwrap()
+        .playing
+    {
+        return;
+    }
+    storage_write(
+        SETTINGS_FILE,
+        &ron::ser::to_string_pretty(&settings(galaxy), ron::ser::PrettyConfig::default()).unwrap(),
+    );
+}
+
+fn step_volume(volume: f64, input: MenuInput) -> f64 {
+    let step = match input {
+        MenuInput::Left => -VOLUME_STEP,
+        MenuInput::Right | MenuInput::Select => VOLUME_STEP,
+        _ => return volume,
+    };
+    ((volume + step) * 10.0).round().clamp(0.0, 10.0) / 10.0
+}
+
+fn settings_main_input(galaxy: &Galaxy, menu: &mut SettingsMenu, input: MenuInput) {
+    let mut settings = galaxy
+        .get_mut_resource::<Settings, _>(Settings::single_resource())
+        .unwrap();
+
+    let changes_value = matches!(
+        input,
+        MenuInput::Left | MenuInput::Right | MenuInput::Select
+    );
+    match SETTINGS_ITEMS[menu.selected] {
+        SettingsItem::MasterVolume => {
+            settings.master_volume = step_volume(settings.master_volume, input);
+        }
+        SettingsItem::EffectsVolume => {
+            settings.effects_volume = step_volume(settings.effects_volume, input);
+        }
+        SettingsItem::WindowMode if changes_value => {
+            settings.window_mode = match settings.window_mode {
+                WindowMode::Windowed => WindowMode::Fullscreen,
+                WindowMode::Fullscreen => WindowMode::Windowed,
+            };
+        }
+        SettingsItem::Difficulty if changes_value => {
+            settings.difficulty = settings.difficulty.cycle(input != MenuInput::Left);
+        }
+        SettingsItem::CustomRules if input == MenuInput::Select => {
+            menu.open_page(SettingsPage::Rules)
+        }
+        SettingsItem::Keys if input == MenuInput::Select => menu.open_page(SettingsPage::Keys),
+        SettingsItem::Back if input == MenuInput::Select => state_pop(galaxy),
+        _ => (),
+    }
+    drop(settings);
+
+    if changes_value {
+        settings_apply(galaxy);
+        if SETTINGS_ITEMS[menu.selected] == SettingsItem::EffectsVolume {
+            audio_coin(galaxy);
+        }
+    }
+}
+
+fn settings_keys_input(galaxy: &Galaxy, menu: &mut SettingsMenu, input: MenuInput) {
+    if input != MenuInput::Select {
+        return;
+    }
+
+    menu.status = None;
+    if let Some(&action) = KeyAction::ALL.get(menu.selected) {
+        menu.rebinding = Some(action);
+    } else if menu.selected == KeyAction::ALL.len() {
+        galaxy
+            .get_mut_resource::<Settings, _>(Settings::single_resource())
+            .unwrap()
+            .keys = KeyBindings::default();
+    } else {
+        menu.open_main_page_at(SettingsItem::Keys);
+    }
+}
+
+fn settings_rules_input(galaxy: &Galaxy, menu: &mut SettingsMenu, input: MenuInput) {
+    let mut settings = galaxy
+        .get_mut_resource::<Settings, _>(Settings::single_resource())
+        .unwrap();
+
+    let normal = tuning(galaxy).difficulty.normal;
+
+    match RuleField::ALL.get(menu.selected) {
+        Some(field) => {
+            let forward = match input {
+                MenuInput::Left => false,
+                MenuInput::Right | MenuInput::Select => true,
+                _ => return,
+            };
+            let rules = settings.custom_rules.get_or_insert(normal);
+            field.step(rules, &normal, forward);
+        }
+        None if input != MenuInput::Select => (),
+        None if menu.selected == RuleField::ALL.len() => settings.custom_rules = None,
+        None => menu.open_main_page_at(SettingsItem::CustomRules),
+    }
+}
+
+pub fn settings_update(galaxy: &Galaxy) {
+    let mut menu = galaxy
+        .get_mut_resource::<SettingsMenu, _>(SettingsMenu::single_resource())
+        .unwrap();
+
+    for input in keyboard_inputs(galaxy) {
+        if let Some(action) = menu.rebinding {
+            // Escape cancels on release, like backing out of any other menu.
+            match (input.virtual_keycode, input.state) {
+                (Some(VirtualKeyCode::Escape), ElementState::Released) => menu.rebinding = None,
+                (Some(VirtualKeyCode::Escape), _) | (None, _) | (_, ElementState::Released) => (),
+                (Some(key), ElementState::Pressed) if key_reserved(key) => {
+                    menu.status = Some(KeyStatus::Reserved(key));
+                }
+                (Some(key), ElementState::Pressed) => {
+                    let mut settings = galaxy
+                        .get_mut_resource::<Settings, _>(Settings::single_resource())
+                        .unwrap();
+                    let old = action.key(settings.keys);
+                    menu.status = action
+                        .bind(&mut settings.keys, key)
+                        .map(|other| KeyStatus::Swapped(other, old));
+                    menu.rebinding = None;
+                }
+            }
+            continue;
+        }
+
+        let Some(input) = menu_input(&input) else {
+            continue;
+        };
+
+        if input == MenuInput::Back {
+            match menu.page {
+                SettingsPage::Main => state_pop(galaxy),
+                SettingsPage::Keys => menu.open_page(SettingsPage::Main),
+                SettingsPage::Rules => menu.open_main_page_at(SettingsItem::CustomRules),
+            }
+            continue;
+        }
+
+        let items = menu.items(&settings(galaxy), &tuning(galaxy).difficulty.normal);
+        menu_navigate(&mut menu.selected, &items, input);
+
+        match menu.page {
+            SettingsPage::Main => settings_main_input(galaxy, &mut menu, input),
+            SettingsPage::Keys => settings_keys_input(galaxy, &mut menu, input),
+            SettingsPage::Rules => settings_rules_input(galaxy, &mut menu, input),
+        }
+    }
+}
+
+pub fn settings_render(galaxy: &Galaxy) {
+    let menu = *galaxy
+        .get_resource::<SettingsMenu, _>(SettingsMenu::single_resource())
+        .unwrap();
+    let title = match menu.page {
+        SettingsPage::Main => "SETTINGS",
+        SettingsPage::Keys => "KEY BINDINGS",
+        SettingsPage::Rules => "CUSTOM RULES",
+    };
+    draw_menu(
+        galaxy,
+        title,
+        &menu.items(&settings(galaxy), &tuning(galaxy).difficulty.normal),
+        menu.selected,
+        BACKDROP_ALPHA,
+    );
+
+    let status = match (menu.status, menu.rebinding) {
+        (Some(KeyStatus::Reserved(key)), _) => format!("{:?} is kept for the game", key),
+        (Some(KeyStatus::Swapped(other, key)), _) => format!("{} moved to {:?}", other.name(), key),
+        (None, Some(_)) => String::from("Press a key, Escape to cancel"),
+        (None, None) => return,
+    };
+    let camera = galaxy
+        .get_resource::<RendererCamera, _>(RendererCamera::single_resource())
+        .unwrap()
+        .position;
+    draw_text(
+        galaxy,
+        &status,
+        camera + glm::vec2(0.0, STATUS_Y),
+        STATUS_SIZE,
+        STATUS_COLOR,
+    );
+}
@@ -0,0 +1,219 @@
+use super::*;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy)]
+pub struct GameStateHooks {
+    pub on_enter: fn(&Galaxy),
+    pub on_exit: fn(&Galaxy),
+    pub update: fn(&Galaxy),
+    /// Runs every frame while the state is anywhere on the stack, after the top state's update.
+    pub render: fn(&Galaxy),
+}
+
+impl GameStateHooks {
+    pub fn new(update: fn(&Galaxy)) -> Self {
+        Self {
+            on_enter: |_| {},
+            on_exit: |_| {},
+            update,
+            render: |_| {},
+        }
+    }
+
+    pub fn on_enter(mut self, on_enter: fn(&Galaxy)) -> Self {
+        self.on_enter = on_enter;
+        self
+    }
+
+    pub fn on_exit(mut self, on_exit: fn(&Galaxy)) -> Self {
+        self.on_exit = on_exit;
+        self
+    }
+
+    pub fn on_render(mut self, render: fn(&Galaxy)) -> Self {
+        self.render = render;
+        self
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StateChangeKind {
+    Set,
+    Push,
+    Pop,
+}
+
+/// Sent whenever the top of the state stack changes.
+#[derive(Event, Clone, Copy)]
+pub struct StateChanged {
+    pub kind: StateChangeKind,
+    pub from: Option<GameState>,
+    pub to: GameState,
+}
+
+#[derive(Clone, Copy)]
+enum StateCommand {
+    Set(GameState),
+    Push(GameState),
+    Pop,
+}
+
+/// The `GameState` resource always mirrors the top of `stack`.
+#[derive(SingleResource, Default)]
+pub struct GameStateMachine {
+    hooks: HashMap<GameState, GameStateHooks>,
+    stack: Vec<GameState>,
+    pending: Vec<StateCommand>,
+}
+
+impl GameStateMachine {
+    pub fn top(&self) -> Option<GameState> {
+        self.stack.last().copied()
+    }
+
+    pub fn contains(&self, state: GameState) -> bool {
+        self.stack.contains(&state)
+    }
+
+    /// The topmost gameplay state, looking past any menus overlaid on it.
+    pub fn gameplay_state(&self) -> Option<GameState> {
+        self.stack
+            .iter()
+            .rev()
+            .copied()
+            .find(|state| matches!(state, GameState::Map | GameState::CandyLand))
+    }
+
+    fn hooks(&self, state: GameState) -> GameStateHooks {
+        *self
+            .hooks
+            .get(&state)
+            .expect("GameState used without being registered")
+    }
+}
+
+pub fn state_machine_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(
+        GameStateMachine::single_resource(),
+        GameStateMachine::default(),
+    );
+}
+
+pub fn state_register(galaxy: &Galaxy, state: GameState, hooks: GameStateHooks) {
+    galaxy
+        .get_mut_resource::<GameStateMachine, _>(GameStateMachine::single_resource())
+        .unwrap()
+        .hooks
+        .insert(state, hooks);
+}
+
+fn state_command(galaxy: &Galaxy, command: StateCommand) {
+    galaxy
+        .get_mut_resource::<GameStateMachine, _>(GameStateMachine::single_resource())
+        .unwrap()
+        .pending
+        .push(command);
+}
+
+/// Exit every state on the stack and replace them with `state`.
+pub fn state_set(galaxy: &Galaxy, state: GameState) {
+    state_command(galaxy, StateCommand::Set(state));
+}
+
+/// Overlay `state` on the current one, which stays on the stack but stops updating.
+pub fn state_push(galaxy: &Galaxy, state: GameState) {
+    state_command(galaxy, StateCommand::Push(state));
+}
+
+/// Exit the top state and resume the one below it.
+pub fn state_pop(galaxy: &Galaxy) {
+    state_command(galaxy, StateCommand::Pop);
+}
+
+/// Whether `state` is on the stack, even if something is overlaid on it.
+pub fn state_on_stack(galaxy: &Galaxy, state: GameState) -> bool {
+    galaxy
+        .get_resource::<GameStateMachine, _>(GameStateMachine::single_resource())
+        .unwrap()
+        .contains(state)
+}
+
+fn state_machine_apply(galaxy: &Galaxy) {
+    // Hooks are free to queue more commands, so the machine can't stay borrowed while they run.
+    loop {
+        let mut machine = galaxy
+            .get_mut_resource::<GameStateMachine, _>(GameStateMachine::single_resource())
+            .unwrap();
+        if machine.pending.is_empty() {
+            return;
+        }
+        let command = machine.pending.remove(0);
+        let from = machine.top();
+
+        let (kind, exits, to) = match command {
+            StateCommand::Set(state) => {
+                let exits = machine.stack.drain(..).rev().collect::<Vec<_>>();
+                machine.stack.push(state);
+                (StateChangeKind::Set, exits, state)
+            }
+            StateCommand::Push(state) => {
+                machine.stack.push(state);
+                (StateChangeKind::Push, vec![], state)
+            }
+            StateCommand::Pop => {
+                if machine.stack.len() < 2 {
+                    continue;
+                }
+                let exit = machine.stack.pop().unwrap();
+                (StateChangeKind::Pop, vec![exit], machine.top().unwrap())
+            }
+        };
+
+        let exit_hooks = exits
+            .iter()
+            .map(|&state| machine.hooks(state))
+            .collect::<Vec<_>>();
+        let enter_hook = (kind != StateChangeKind::Pop).then(|| machine.hooks(to));
+        drop(machine);
+
+        for hooks in exit_hooks {
+            (hooks.on_exit)(galaxy);
+        }
+
+        **galaxy
+            .get_mut_resource::<GameState, _>(GameState::single_resource())
+            .unwrap() = to;
+
+        if let Some(hooks) = enter_hook {
+            (hooks.on_enter)(galaxy);
+        }
+
+        galaxy.insert_event(StateChanged { kind, from, to });
+    }
+}
+
+pub fn game_state_machine_update(galaxy: &Galaxy) {
+    state_machine_apply(galaxy);
+
+    let (update, renders) = {
+        let machine = galaxy
+            .get_resource::<GameStateMachine, _>(GameStateMachine::single_resource())
+            .unwrap();
+        (
+            machine.top().map(|state| machine.hooks(state).update),
+            machine
+                .stack
+                .iter()
+                .map(|&state| machine.hooks(state).render)
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    if let Some(update) = update {
+        update(galaxy);
+    }
+    // Bottom to top, so overlays draw over the states they cover.
+    for render in renders {
+        render(galaxy);
+    }
+}
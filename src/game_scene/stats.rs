@@ -0,0 +1,60 @@
+use super::*;
+
+/// Running totals for the current run, kept for the high score table.
+#[derive(SingleResource, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct RunStats {
+    pub started: GameInstant,
+    pub houses_raided: usize,
+    pub kids_punched: usize,
+    /// Candy taken back by angry grannies and the neighborhood watch.
+    pub candy_lost: usize,
+    /// When the score first reached the target score.
+    pub reached_target: Option<GameInstant>,
+}
+
+pub fn stats_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(RunStats::single_resource(), RunStats::default());
+}
+
+pub fn stats_reset(galaxy: &Galaxy) {
+    stats_restore(
+        galaxy,
+        RunStats {
+            started: game_now(galaxy),
+            ..Default::default()
+        },
+    );
+}
+
+pub fn stats_restore(galaxy: &Galaxy, stats: RunStats) {
+    **galaxy
+        .get_mut_resource::<RunStats, _>(RunStats::single_resource())
+        .unwrap() = stats;
+}
+
+pub fn stats(galaxy: &Galaxy) -> RunStats {
+    *galaxy
+        .get_resource::<RunStats, _>(RunStats::single_resource())
+        .unwrap()
+}
+
+pub fn stats_record(galaxy: &Galaxy, record: impl FnOnce(&mut RunStats)) {
+    record(
+        &mut galaxy
+            .get_mut_resource::<RunStats, _>(RunStats::single_resource())
+            .unwrap(),
+    );
+}
+
+/// Count what the state changes say about the run.
+pub fn stats_update(galaxy: &Galaxy) {
+    for change in galaxy.get_events::<StateChanged>() {
+        // Continuing a run saved inside a house comes from the title screen instead.
+        let raided = change.kind == StateChangeKind::Set
+            && change.from == Some(GameState::Map)
+            && change.to == GameState::CandyLand;
+        if raided {
+            stats_record(galaxy, |stats| stats.houses_raided += 1);
+        }
+    }
+}
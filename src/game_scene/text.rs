@@ -0,0 +1,43 @@
+use super::*;
+
+const FONT_GRID: usize = 16;
+const GLYPH_ADVANCE: f32 = 0.6;
+
+pub fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * size * GLYPH_ADVANCE
+}
+
+/// Draws `text` with the bitmap font, centered on `position`.
+pub fn draw_text(galaxy: &Galaxy, text: &str, position: glm::Vec2, size: f32, color: glm::Vec4) {
+    let advance = size * GLYPH_ADVANCE;
+    let start_x = position.x - text_width(text, size) * 0.5 + advance * 0.5;
+
+    for (i, c) in text.chars().enumerate() {
+        let c = if c.is_ascii() {
+            c as usize
+        } else {
+            '?' as usize
+        };
+        if c == ' ' as usize {
+            continue;
+        }
+
+        let cell = 1.0 / FONT_GRID as f32;
+        galaxy.insert_event(RendererDrawSprite(Sprite {
+            texture_index: SPRITE_FONT,
+            color,
+            // The atlas has a black background.
+            blend: SpriteBlend::Additive,
+            scale: glm::vec2(size, size),
+            position: glm::vec2(start_x + i as f32 * advance, position.y),
+            uv: glm::vec4(
+                (c % FONT_GRID) as f32 * cell,
+                (c / FONT_GRID) as f32 * cell,
+                cell,
+                cell,
+            ),
+            unlit: true,
+            ..Default::default()
+        }));
+    }
+}
@@ -0,0 +1,161 @@
+use super::*;
+
+const TRANSITION_DURATION: Duration = Duration::from_millis(800);
+const SCREEN_SIZE: f32 = 2.2;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TransitionKind {
+    /// Fade to black.
+    Fade,
+    /// Circle closing in on the camera, which follows the player on the map.
+    Iris,
+    /// Two panels sliding shut from the sides, like a front door.
+    Door,
+}
+
+#[derive(Clone, Copy)]
+struct ActiveTransition {
+    kind: TransitionKind,
+    target: GameState,
+    on_switch: fn(&Galaxy),
+    started: GameInstant,
+    switched: bool,
+}
+
+#[derive(SingleResource, Default, Clone, Copy)]
+pub struct Transition(Option<ActiveTransition>);
+
+pub fn transition_init(galaxy: &Galaxy) {
+    galaxy.insert_resource(Transition::single_resource(), Transition::default());
+}
+
+/// Start moving to `target`, calling `state_set` once the screen is fully covered.
+/// Returns `false` if a transition is already running.
+pub fn transition_to(galaxy: &Galaxy, kind: TransitionKind, target: GameState) -> bool {
+    transition_to_then(galaxy, kind, target, |_| {})
+}
+
+/// Like `transition_to`, but also runs `on_switch` while the screen is covered.
+pub fn transition_to_then(
+    galaxy: &Galaxy,
+    kind: TransitionKind,
+    target: GameState,
+    on_switch: fn(&Galaxy),
+) -> bool {
+    let mut transition = galaxy
+        .get_mut_resource::<Transition, _>(Transition::single_resource())
+        .unwrap();
+
+    if transition.0.is_some() {
+        return false;
+    }
+
+    transition.0 = Some(ActiveTransition {
+        kind,
+        target,
+        on_switch,
+        started: real_now(galaxy),
+        switched: false,
+    });
+    true
+}
+
+pub fn transition_active(galaxy: &Galaxy) -> bool {
+    galaxy
+        .get_resource::<Transition, _>(Transition::single_resource())
+        .unwrap()
+        .0
+        .is_some()
+}
+
+pub fn transition_update(galaxy: &Galaxy) {
+    let mut transition = galaxy
+        .get_mut_resource::<Transition, _>(Transition::single_resource())
+        .unwrap();
+
+    let Some(active) = transition.0.as_mut() else {
+        return;
+    };
+
+    let t = real_now(galaxy)
+        .duration_since(active.started)
+        .as_secs_f32()
+        / TRANSITION_DURATION.as_secs_f32();
+
+    if t >= 0.5 && !active.switched {
+        active.switched = true;
+        (active.on_switch)(galaxy);
+        state_set(galaxy, active.target);
+    }
+
+    if t >= 1.0 {
+        transition.0 = None;
+        return;
+    }
+
+    let center = galaxy
+        .get_resource::<RendererCamera, _>(RendererCamera::single_resource())
+        .unwrap()
+        .position;
+
+    // 0.0 when the screen is clear, 1.0 when fully covered.
+    let cover = 1.0 - (t * 2.0 - 1.0).abs();
+
+    match active.kind {
+        TransitionKind::Fade => {
+            draw_black(galaxy, center, glm::vec2(SCREEN_SIZE, SCREEN_SIZE), cover);
+        }
+        TransitionKind::Iris => {
+            // The hole is half the sprite's width across, so the sprite is surrounded by panels
+            // to keep the rest of the screen covered.
+            let size = (1.0 - cover) * SCREEN_SIZE * 2.0;
+            galaxy.insert_event(RendererDrawSprite(Sprite {
+                texture_index: SPRITE_IRIS,
+                color: glm::vec4(0.0, 0.0, 0.0, 1.0),
+                scale: glm::vec2(size, size),
+                position: center,
+                unlit: true,
+                ..Default::default()
+            }));
+            let offset = (size + SCREEN_SIZE) * 0.5;
+            for side in [
+                glm::vec2(-offset, 0.0),
+                glm::vec2(offset, 0.0),
+                glm::vec2(0.0, -offset),
+                glm::vec2(0.0, offset),
+            ] {
+                draw_black(
+                    galaxy,
+                    center + side,
+                    glm::vec2(SCREEN_SIZE, SCREEN_SIZE),
+                    1.0,
+                );
+            }
+        }
+        TransitionKind::Door => {
+            let panel = glm::vec2(SCREEN_SIZE * 0.5, SCREEN_SIZE);
+            let offset = panel.x * (0.5 + (1.0 - cover));
+            draw_black(galaxy, center - glm::vec2(offset, 0.0), panel, 1.0);
+            draw_black(galaxy, center + glm::vec2(offset, 0.0), panel, 1.0);
+        }
+    }
+}
+
+fn draw_black(galaxy: &Galaxy, position: glm::Vec2, scale: glm::Vec2, alpha: f32) {
+    galaxy.insert_event(RendererDrawSprite(Sprite {
+        texture_index: SPRITE_WHITE,
+        color: glm::vec4(0.0, 0.0, 0.0, alpha),
+        scale,
+        position,
+        unlit: true,
+        ..Default::default()
+    }));
+}
+
+/// Opaque square with a transparent circle cut out of the middle.
+pub fn iris_texture() -> TextureData {
+    TextureData::from_fn(128, 128, |x, y| {
+        let alpha = if (x * x + y * y).sqrt() < 0.5 { 0 } else { 255 };
+        [255, 255, 255, alpha]
+    })
+}
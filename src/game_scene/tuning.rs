@@ -0,0 +1,645 @@
+use super::*;
+
+/// Shipped with the game. Anything missing from it falls back to the defaults below.
+const TUNING_DEFAULT: &str = include_str!("../../assets/tuning.ron");
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NpcTuning {
+    pub walk_speed: f32,
+    pub house_idle_time: Duration,
+    pub player_punch_range: f32,
+    pub crying_slide_speed: f32,
+    pub crying_time: Duration,
+    /// How long kids remember the player after last seeing them up to no good.
+    pub memory_time: Duration,
+    /// Scared kids run from a player this close.
+    pub flee_radius: f32,
+    /// How hard a gang of angry kids shoves the player.
+    pub shove_speed: f32,
+    /// Scared kids stop running once the player is this much further than `flee_radius`.
+    pub flee_safe_scale: f32,
+    pub flee_speed_scale: f32,
+    pub revenge_speed_scale: f32,
+    /// Angry kids only go after the player with this many of them, counting themselves.
+    pub gang_size: usize,
+    pub gang_radius: f32,
+    /// Chance each tick that a big enough gang actually goes for it.
+    pub gang_chance: f64,
+    /// Angry kids give up on getting a shove in after this long.
+    pub revenge_time: Duration,
+    pub shove_range: f32,
+    /// How long a kid stands yelling for a grown up.
+    pub call_time: Duration,
+}
+
+impl Default for NpcTuning {
+    fn default() -> Self {
+        Self {
+            walk_speed: 0.01,
+            house_idle_time: Duration::from_secs(2),
+            player_punch_range: 0.6,
+            crying_slide_speed: 0.001,
+            crying_time: Duration::from_secs(5),
+            memory_time: Duration::from_secs(60),
+            flee_radius: 1.2,
+            shove_speed: 0.03,
+            flee_safe_scale: 1.5,
+            flee_speed_scale: 1.5,
+            revenge_speed_scale: 1.3,
+            gang_size: 3,
+            gang_radius: 1.5,
+            gang_chance: 0.01,
+            revenge_time: Duration::from_secs(6),
+            shove_range: 0.3,
+            call_time: Duration::from_secs(2),
+        }
+    }
+}
+
+/// How kids feel about the player, see `NpcMind`. Fear and anger go from 0 to 1.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MindTuning {
+    /// Fear and anger keep this much of themselves every tick.
+    pub feeling_decay: f32,
+    pub punch_fear: f32,
+    pub punch_anger: f32,
+    pub witness_fear: f32,
+    pub witness_anger: f32,
+    /// Kids this close to a punch see it happen.
+    pub witness_radius: f32,
+    /// Kids tell everyone this close what they saw.
+    pub warn_radius: f32,
+    /// How much of what they feel kids get across when telling others.
+    pub warn_share: f32,
+    /// News that would leave someone feeling less than this isn't worth passing on.
+    pub warn_min_feeling: f32,
+    /// Kids who remember the player get this much more worked up every tick the player is close.
+    pub recognize_feeling: f32,
+    pub flee_fear: f32,
+    pub call_fear: f32,
+    pub revenge_anger: f32,
+    /// How much fear is left once a grown up is on the way.
+    pub reassured_fear: f32,
+    /// How much anger is left after getting a shove in.
+    pub vented_anger: f32,
+}
+
+impl Default for MindTuning {
+    fn default() -> Self {
+        Self {
+            feeling_decay: 0.998,
+            punch_fear: 0.9,
+            punch_anger: 0.8,
+            witness_fear: 0.5,
+            witness_anger: 0.4,
+            witness_radius: 1.2,
+            warn_radius: 1.0,
+            warn_share: 0.6,
+            warn_min_feeling: 0.15,
+            recognize_feeling: 0.01,
+            flee_fear: 0.35,
+            call_fear: 0.6,
+            revenge_anger: 0.5,
+            reassured_fear: 0.5,
+            vented_anger: 0.3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CandyTuning {
+    pub friction_factor: f32,
+    pub pickup_range: f32,
+    /// Candy inside this range gets nudged away from the player.
+    pub movement_range: f32,
+    pub movement_scalar: f32,
+    /// Blown candy keeps sliding until it has covered this distance.
+    pub max_travel: f32,
+}
+
+impl Default for CandyTuning {
+    fn default() -> Self {
+        Self {
+            friction_factor: 0.001,
+            pickup_range: 0.2,
+            movement_range: 2.0,
+            movement_scalar: 0.0005,
+            max_travel: 0.01,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HouseTuning {
+    pub interact_distance: f32,
+    pub lantern_chance: f64,
+    /// How long an angry granny stays at the door.
+    pub anger_duration: Duration,
+    /// Candy an angry granny takes back.
+    pub granny_penalty: usize,
+    /// Grannies don't notice anything until this much has gone missing.
+    pub min_stolen_for_anger: usize,
+}
+
+impl Default for HouseTuning {
+    fn default() -> Self {
+        Self {
+            interact_distance: 0.5,
+            lantern_chance: 0.35,
+            anger_duration: Duration::from_secs(3),
+            granny_penalty: 6,
+            min_stolen_for_anger: 5,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotorietyTuning {
+    pub punch: f32,
+    /// Getting caught by a granny.
+    pub granny: f32,
+    /// Taken off when the neighborhood watch confiscates candy.
+    pub confiscate: f32,
+    /// Candy the neighborhood watch takes each time it catches up with the player.
+    pub confiscate_amount: usize,
+    /// How long a watch adult leaves the player be after taking candy.
+    pub confiscate_cooldown: Duration,
+    pub watch_speed: f32,
+    /// Close enough to the player to take candy off them.
+    pub watch_contact_range: f32,
+    pub decay_per_second: f32,
+    /// How long after the last incident it starts dying down.
+    pub decay_delay: Duration,
+    /// Another watch adult comes out at each of these.
+    pub watch_thresholds: [f32; 3],
+    /// Houses stop opening the door from here on.
+    pub refuse_threshold: f32,
+}
+
+impl Default for NotorietyTuning {
+    fn default() -> Self {
+        Self {
+            punch: 15.0,
+            granny: 20.0,
+            confiscate: 10.0,
+            confiscate_amount: 5,
+            confiscate_cooldown: Duration::from_secs(5),
+            watch_speed: 0.008,
+            watch_contact_range: 0.3,
+            decay_per_second: 1.0,
+            decay_delay: Duration::from_secs(10),
+            watch_thresholds: [25.0, 50.0, 75.0],
+            refuse_threshold: 60.0,
+        }
+    }
+}
+
+/// How one kind of grown up walks, watches and catches. Whatever tuning.ron leaves out is
+/// taken from a parent.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdultProfile {
+    pub walk_speed: f32,
+    pub chase_speed: f32,
+    pub sight_range: f32,
+    /// Half the width of the vision cone, in radians.
+    pub sight_half_angle: f32,
+    /// Candy taken off the player when they get caught.
+    pub confiscate: usize,
+}
+
+impl Default for AdultProfile {
+    fn default() -> Self {
+        Self {
+            walk_speed: 0.007,
+            chase_speed: 0.016,
+            sight_range: 1.6,
+            sight_half_angle: 0.7,
+            confiscate: 3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdultTuning {
+    /// Close enough to a patrol point, or to a kid calling for help, to move on.
+    pub arrive_radius: f32,
+    /// Parents hear kids calling for help from this far away.
+    pub hearing_radius: f32,
+    /// A chase goes on this long after losing sight of the player, heading for where they
+    /// were last seen.
+    pub lose_time: Duration,
+    pub catch_range: f32,
+    pub catch_cooldown: Duration,
+    /// How much of the way to the new heading adults turn every tick.
+    pub turn_rate: f32,
+    pub parent: AdultProfile,
+    pub police: AdultProfile,
+}
+
+impl Default for AdultTuning {
+    fn default() -> Self {
+        Self {
+            arrive_radius: 0.3,
+            hearing_radius: 3.0,
+            lose_time: Duration::from_secs(3),
+            catch_range: 0.3,
+            catch_cooldown: Duration::from_secs(4),
+            turn_rate: 0.15,
+            parent: AdultProfile::default(),
+            police: AdultProfile {
+                walk_speed: 0.008,
+                chase_speed: 0.019,
+                sight_range: 2.4,
+                sight_half_angle: 0.5,
+                confiscate: 8,
+            },
+        }
+    }
+}
+
+/// The rules behind each difficulty preset.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DifficultyTuning {
+    pub easy: GameRules,
+    pub normal: GameRules,
+    pub hard: GameRules,
+}
+
+impl Default for DifficultyTuning {
+    fn default() -> Self {
+        Self {
+            easy: GameRules::preset(Difficulty::Easy),
+            normal: GameRules::preset(Difficulty::Normal),
+            hard: GameRules::preset(Difficulty::Hard),
+        }
+    }
+}
+
+impl DifficultyTuning {
+    pub fn preset(&self, difficulty: Difficulty) -> GameRules {
+        match difficulty {
+            Difficulty::Easy => self.easy,
+            Difficulty::Normal | Difficulty::Custom => self.normal,
+            Difficulty::Hard => self.hard,
+        }
+    }
+}
+
+#[derive(SingleResource, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tuning {
+    pub npc: NpcTuning,
+    pub mind: MindTuning,
+    pub candy: CandyTuning,
+    pub house: HouseTuning,
+    pub notoriety: NotorietyTuning,
+    pub adults: AdultTuning,
+    pub difficulty: DifficultyTuning,
+}
+
+impl Tuning {
+    fn parse(data: &str) -> Result<Self, String> {
+        let tuning = ron::from_str::<Tuning>(data).map_err(|e| e.to_string())?;
+        tuning.validate()?;
+        Ok(tuning)
+    }
+
+    /// Catch values that would break the game rather than just make it play oddly.
+    fn validate(&self) -> Result<(), String> {
+        let mut errors = vec![];
+        let mut check = |ok: bool, name: &str, requirement: &str| {
+            if !ok {
+                errors.push(format!("{} must be {}", name, requirement));
+            }
+        };
+
+        check(self.npc.walk_speed > 0.0, "npc.walk_speed", "positive");
+        check(
+            self.npc.player_punch_range > 0.0,
+            "npc.player_punch_range",
+            "positive",
+        );
+        check(
+            self.npc.crying_slide_speed >= 0.0,
+            "npc.crying_slide_speed",
+            "at least 0",
+        );
+        check(self.npc.flee_radius > 0.0, "npc.flee_radius", "positive");
+        check(self.npc.shove_speed >= 0.0, "npc.shove_speed", "at least 0");
+        check(
+            self.npc.flee_safe_scale >= 1.0,
+            "npc.flee_safe_scale",
+            "at least 1",
+        );
+        check(
+            self.npc.flee_speed_scale > 0.0,
+            "npc.flee_speed_scale",
+            "positive",
+        );
+        check(
+            self.npc.revenge_speed_scale > 0.0,
+            "npc.revenge_speed_scale",
+            "positive",
+        );
+        check(self.npc.gang_radius >= 0.0, "npc.gang_radius", "at least 0");
+        check(
+            (0.0..=1.0).contains(&self.npc.gang_chance),
+            "npc.gang_chance",
+            "between 0 and 1",
+        );
+        check(self.npc.shove_range >= 0.0, "npc.shove_range", "at least 0");
+
+        for (value, name) in [
+            (self.mind.feeling_decay, "mind.feeling_decay"),
+            (self.mind.punch_fear, "mind.punch_fear"),
+            (self.mind.punch_anger, "mind.punch_anger"),
+            (self.mind.witness_fear, "mind.witness_fear"),
+            (self.mind.witness_anger, "mind.witness_anger"),
+            (self.mind.warn_share, "mind.warn_share"),
+            (self.mind.warn_min_feeling, "mind.warn_min_feeling"),
+            (self.mind.recognize_feeling, "mind.recognize_feeling"),
+            (self.mind.flee_fear, "mind.flee_fear"),
+            (self.mind.call_fear, "mind.call_fear"),
+            (self.mind.revenge_anger, "mind.revenge_anger"),
+            (self.mind.reassured_fear, "mind.reassured_fear"),
+            (self.mind.vented_anger, "mind.vented_anger"),
+        ] {
+            check((0.0..=1.0).contains(&value), name, "between 0 and 1");
+        }
+        check(
+            self.mind.witness_radius >= 0.0,
+            "mind.witness_radius",
+            "at least 0",
+        );
+        check(
+            self.mind.warn_radius >= 0.0,
+            "mind.warn_radius",
+            "at least 0",
+        );
+        check(
+            (0.0..1.0).contains(&self.candy.friction_factor),
+            "candy.friction_factor",
+            "between 0 and 1",
+        );
+        check(
+            self.candy.pickup_range > 0.0,
+            "candy.pickup_range",
+            "positive",
+        );
+        check(
+            self.candy.movement_range >= 0.0,
+            "candy.movement_range",
+            "at least 0",
+        );
+        check(
+            self.candy.movement_scalar >= 0.0,
+            "candy.movement_scalar",
+            "at least 0",
+        );
+        check(
+            self.candy.max_travel >= 0.0,
+            "candy.max_travel",
+            "at least 0",
+        );
+        check(
+            self.house.interact_distance > 0.0,
+            "house.interact_distance",
+            "positive",
+        );
+        check(
+            (0.0..=1.0).contains(&self.house.lantern_chance),
+            "house.lantern_chance",
+            "between 0 and 1",
+        );
+        check(
+            self.house.min_stolen_for_anger > 0,
+            "house.min_stolen_for_anger",
+            "positive",
+        );
+
+        for (value, name) in [
+            (self.notoriety.punch, "notoriety.punch"),
+            (self.notoriety.granny, "notoriety.granny"),
+            (self.notoriety.confiscate, "notoriety.confiscate"),
+            (
+                self.notoriety.decay_per_second,
+                "notoriety.decay_per_second",
+            ),
+        ] {
+            check(value >= 0.0, name, "at least 0");
+        }
+        check(
+            self.notoriety.watch_speed > 0.0,
+            "notoriety.watch_speed",
+            "positive",
+        );
+        check(
+            self.notoriety.watch_contact_range >= 0.0,
+            "notoriety.watch_contact_range",
+            "at least 0",
+        );
+        check(
+            self.notoriety
+                .watch_thresholds
+                .iter()
+                .all(|threshold| (0.0..=NOTORIETY_MAX).contains(threshold)),
+            "notoriety.watch_thresholds",
+            "between 0 and 100",
+        );
+        check(
+            (0.0..=NOTORIETY_MAX).contains(&self.notoriety.refuse_threshold),
+            "notoriety.refuse_threshold",
+            "between 0 and 100",
+        );
+
+        check(
+            self.adults.arrive_radius > 0.0,
+            "adults.arrive_radius",
+            "positive",
+        );
+        check(
+            self.adults.hearing_radius >= 0.0,
+            "adults.hearing_radius",
+            "at least 0",
+        );
+        check(
+            self.adults.catch_range >= 0.0,
+            "adults.catch_range",
+            "at least 0",
+        );
+        check(
+            self.adults.turn_rate > 0.0 && self.adults.turn_rate <= 1.0,
+            "adults.turn_rate",
+            "above 0 and at most 1",
+        );
+        for (name, profile) in [
+            ("parent", self.adults.parent),
+            ("police", self.adults.police),
+        ] {
+            let mut check = |ok: bool, field: &str, requirement: &str| {
+                check(ok, &format!("adults.{}.{}", name, field), requirement)
+            };
+            check(profile.walk_speed > 0.0, "walk_speed", "positive");
+            check(profile.chase_speed > 0.0, "chase_speed", "positive");
+            check(profile.sight_range >= 0.0, "sight_range", "at least 0");
+            check(
+                (0.0..=std::f32::consts::PI).contains(&profile.sight_half_angle),
+                "sight_half_angle",
+                "between 0 and pi",
+            );
+        }
+
+        for (name, rules) in [
+            ("easy", self.difficulty.easy),
+            ("normal", self.difficulty.normal),
+            ("hard", self.difficulty.hard),
+        ] {
+            let mut check = |ok: bool, field: &str, requirement: &str| {
+                check(ok, &format!("difficulty.{}.{}", name, field), requirement)
+            };
+            check(rules.win_score > 0, "win_score", "positive");
+            // Otherwise a granny never gets angry, however much is taken.
+            check(
+                rules.steal_max >= self.house.min_stolen_for_anger,
+                "steal_max",
+                "at least house.min_stolen_for_anger",
+            );
+            check(
+                !rules.round_duration.is_zero(),
+                "round_duration",
+                "positive",
+            );
+            check(
+                (0.0..=1.0).contains(&rules.anger_chance),
+                "anger_chance",
+                "between 0 and 1",
+            );
+            check(
+                rules.player_acceleration > 0.0,
+                "player_acceleration",
+                "positive",
+            );
+            check(
+                (0.0..1.0).contains(&rules.player_friction),
+                "player_friction",
+                "between 0 and 1",
+            );
+            check(rules.player_max_speed > 0.0, "player_max_speed", "positive");
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join(", "))
+        }
+    }
+}
+
+pub fn tuning_init(galaxy: &Galaxy) {
+    let tuning = Tuning::parse(TUNING_DEFAULT).expect("Invalid assets/tuning.ron");
+    galaxy.insert_resource(Tuning::single_resource(), tuning);
+    tuning_watch_init(galaxy);
+}
+
+pub fn tuning(galaxy: &Galaxy) -> Tuning {
+    *galaxy
+        .get_resource::<Tuning, _>(Tuning::single_resource())
+        .unwrap()
+}
+
+/// Debug builds on desktop watch the tuning file in the source tree, so designers can tweak
+/// values while the game runs. Bad edits are reported and the previous values are kept.
+/// Difficulty presets only change with the next run, since a run keeps its `GameRules`.
+/// Reloads are recorded for replays, which play them back instead of watching the file.
+#[cfg(all(
+    debug_assertions,
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+mod watch {
+    use super::*;
+    use instant::Instant;
+    use std::time::SystemTime;
+
+    const TUNING_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/tuning.ron");
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    #[derive(SingleResource)]
+    struct TuningWatch {
+        modified: Option<SystemTime>,
+        last_poll: Instant,
+    }
+
+    fn modified() -> Option<SystemTime> {
+        std::fs::metadata(TUNING_PATH).ok()?.modified().ok()
+    }
+
+    pub fn tuning_watch_init(galaxy: &Galaxy) {
+        galaxy.insert_resource(
+            TuningWatch::single_resource(),
+            TuningWatch {
+                modified: modified(),
+                last_poll: Instant::now(),
+            },
+        );
+    }
+
+    pub fn tuning_reload_update(galaxy: &Galaxy) {
+        if galaxy
+            .get_resource::<ReplayStatus, _>(ReplayStatus::single_resource())
+            .unwrap()
+            .playing
+        {
+            return;
+        }
+        let mut watch = galaxy
+            .get_mut_resource::<TuningWatch, _>(TuningWatch::single_resource())
+            .unwrap();
+        if watch.last_poll.elapsed() < POLL_INTERVAL {
+            return;
+        }
+        watch.last_poll = Instant::now();
+
+        let modified = modified();
+        if modified == watch.modified {
+            return;
+        }
+        watch.modified = modified;
+
+        let tuning = std::fs::read_to_string(TUNING_PATH)
+            .map_err(|e| e.to_string())
+            .and_then(|data| Tuning::parse(&data));
+        match tuning {
+            Ok(tuning) => {
+                **galaxy
+                    .get_mut_resource::<Tuning, _>(Tuning::single_resource())
+                    .unwrap() = tuning;
+                replay_setup::replay_setup_record(galaxy);
+                eprintln!("Reloaded {}", TUNING_PATH);
+            }
+            Err(e) => eprintln!("Ignoring {}: {}", TUNING_PATH, e),
+        }
+    }
+}
+
+#[cfg(not(all(
+    debug_assertions,
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+)))]
+mod watch {
+    use super::*;
+
+    pub fn tuning_watch_init(_galaxy: &Galaxy) {}
+
+    pub fn tuning_reload_update(_galaxy: &Galaxy) {}
+}
+
+pub use watch::tuning_reload_update;
+use watch::tuning_watch_init;
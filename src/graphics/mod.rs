@@ -5,7 +5,8 @@ mod render;
 mod texture;
 
 pub use render::{
-    graphics_init, graphics_update, RendererCamera, RendererDrawSprite, RendererInitLoadTextures,
+    graphics_init, graphics_update, RendererAmbientLight, RendererCamera, RendererDrawLight,
+    RendererDrawSprite, RendererInitLoadTextures,
 };
 pub use texture::TextureData;
 
@@ -15,14 +16,58 @@ pub struct Scene {
     view: glm::Mat4,
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Clone, Copy)]
 pub struct Sprite {
-    pub visible: f32,
     pub texture_index: usize,
-    pub color: glm::Vec3,
+    /// RGBA tint. An alpha of zero skips the sprite entirely.
+    pub color: glm::Vec4,
+    pub blend: SpriteBlend,
     pub rotation: f32,
     pub scale: glm::Vec2,
     pub position: glm::Vec2,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Texture region as offset (xy) and size (zw) in texture coordinates.
+    pub uv: glm::Vec4,
+    /// Skip the lighting pass, for HUD and full screen images.
+    pub unlit: bool,
+}
+
+impl Default for Sprite {
+    fn default() -> Self {
+        Self {
+            texture_index: 0,
+            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
+            blend: SpriteBlend::default(),
+            rotation: 0.0,
+            scale: glm::vec2(1.0, 1.0),
+            position: glm::vec2(0.0, 0.0),
+            flip_x: false,
+            flip_y: false,
+            uv: glm::vec4(0.0, 0.0, 1.0, 1.0),
+            unlit: false,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum SpriteBlend {
+    #[default]
+    Alpha,
+    Additive,
+    Multiply,
+}
+
+impl SpriteBlend {
+    pub const COUNT: usize = 3;
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct Light {
+    pub position: glm::Vec2,
+    pub radius: f32,
+    pub color: glm::Vec3,
+    pub intensity: f32,
 }
 
 #[rustfmt::skip]
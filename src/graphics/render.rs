@@ -1,7 +1,9 @@
 use super::*;
 use raw_window_handle::{RawDisplayHandle, RawWindowHandle};
 
-const MAX_SPRITES: usize = 512;
+const MAX_SPRITES: usize = 1024;
+const MAX_LIGHTS: usize = 128;
+const LIGHT_TEXTURE_SIZE: usize = 64;
 
 #[derive(SingleResource)]
 pub struct RendererInitLoadTextures(pub Vec<TextureData>);
@@ -14,14 +16,21 @@ pub struct RendererCamera {
 #[derive(Event)]
 pub struct RendererDrawSprite(pub Sprite);
 
+#[derive(Event)]
+pub struct RendererDrawLight(pub Light);
+
+/// Darkness level for lit sprites this frame. Defaults to fully lit when not sent.
+#[derive(Event)]
+pub struct RendererAmbientLight(pub glm::Vec3);
+
 #[derive(SingleResource)]
 pub struct RendererRes(Renderer);
 
 #[derive(Default, Clone, Copy)]
 struct SpriteUniform {
     pub model: glm::Mat4,
-    pub color: glm::Vec3,
-    pub visible: f32,
+    pub color: glm::Vec4,
+    pub uv: glm::Vec4,
 }
 
 pub struct Renderer {
@@ -36,8 +45,14 @@ pub struct Renderer {
     sprite_ubo: DynamicUniformBufferId,
     sprite_ubo_guard: DynamicUniformBufferTypeGuard<SpriteUniform>,
 
-    programs: Vec<ProgramId>,
+    lit_programs: Vec<[ProgramId; SpriteBlend::COUNT]>,
+    unlit_programs: Vec<[ProgramId; SpriteBlend::COUNT]>,
+    light_program: ProgramId,
+    composite_program: ProgramId,
+
     output_attachment: PassLocalAttachment,
+    scene_attachment: PassLocalAttachment,
+    light_attachment: PassLocalAttachment,
     compiled_pass: CompiledPassId,
 }
 
@@ -67,25 +82,26 @@ impl Renderer {
 
         let mut context = Context::new(extensions, None).unwrap();
 
-        let vs = include_bytes!("shaders/vs.spv");
-        let fs = include_bytes!("shaders/fs.spv");
-
-        let vs = context
-            .naga_translate_shader_code(
-                naga_translation::NagaTranslationStage::Vertex,
-                naga_translation::NagaTranslationInput::Spirv,
-                vs,
-                naga_translation::NagaTranslationExtensionTranslateShaderCodeExt::default(),
-            )
-            .unwrap();
-        let fs = context
-            .naga_translate_shader_code(
-                naga_translation::NagaTranslationStage::Fragment,
-                naga_translation::NagaTranslationInput::Spirv,
-                fs,
-                naga_translation::NagaTranslationExtensionTranslateShaderCodeExt::default(),
-            )
-            .unwrap();
+        let vs = translate_shader(
+            &mut context,
+            naga_translation::NagaTranslationStage::Vertex,
+            include_bytes!("shaders/vs.spv"),
+        );
+        let fs = translate_shader(
+            &mut context,
+            naga_translation::NagaTranslationStage::Fragment,
+            include_bytes!("shaders/fs.spv"),
+        );
+        let composite_vs = translate_shader(
+            &mut context,
+            naga_translation::NagaTranslationStage::Vertex,
+            include_bytes!("shaders/composite_vs.spv"),
+        );
+        let composite_fs = translate_shader(
+            &mut context,
+            naga_translation::NagaTranslationStage::Fragment,
+            include_bytes!("shaders/composite_fs.spv"),
+        );
 
         let shader_set = ShaderSet::shaders(&[
             (
@@ -94,6 +110,13 @@ impl Renderer {
             ),
             (ShaderType::Fragment, &fs),
         ]);
+        let composite_shader_set = ShaderSet::shaders(&[
+            (
+                ShaderType::Vertex(VertexBufferInput { args: vec![2, 2] }),
+                &composite_vs,
+            ),
+            (ShaderType::Fragment, &composite_fs),
+        ]);
 
         let sampler = context.get_sampler(None).unwrap();
 
@@ -101,11 +124,28 @@ impl Renderer {
             context.new_uniform_buffer(&Scene::default(), None).unwrap();
 
         let (sprite_ubo, sprite_ubo_guard) = context
-            .new_dynamic_uniform_buffer(&[SpriteUniform::default(); MAX_SPRITES], None)
+            .new_dynamic_uniform_buffer(&[SpriteUniform::default(); MAX_SPRITES + MAX_LIGHTS], None)
             .unwrap();
 
-        let programs = textures
+        // Sprites are drawn into `scene_attachment` and lights are accumulated into
+        // `light_attachment`. The two are multiplied together onto the surface, then unlit
+        // sprites are drawn on top.
+        let mut pass = Pass::new(
+            width,
+            height,
+            Some(NewPassExt {
+                depends_on_surface_size: Some(()),
+                surface_attachment_load_op: Some(PassInputLoadOpColorType::Clear),
+            }),
+        );
+
+        let output_attachment = pass.get_surface_local_attachment();
+        let scene_attachment = pass.add_attachment_color_image(None);
+        let light_attachment = pass.add_attachment_color_image(None);
+
+        let texture_ids = textures
             .iter()
+            .chain(std::iter::once(&light_texture()))
             .map(|texture| {
                 let texture_id = context
                     .new_texture(texture.width, texture.height, TextureFormat::Rgba, None)
@@ -113,47 +153,85 @@ impl Renderer {
                 context
                     .upload_texture(texture_id, &texture.data, None)
                     .unwrap();
-
-                context
-                    .new_program(
-                        &shader_set,
-                        &[
-                            ShaderUniform {
-                                set: 0,
-                                binding: 0,
-                                ty: ShaderUniformType::UniformBuffer(scene_ubo),
-                            },
-                            ShaderUniform {
-                                set: 1,
-                                binding: 0,
-                                ty: ShaderUniformType::DynamicUniformBuffer(sprite_ubo),
-                            },
-                            ShaderUniform {
-                                set: 2,
-                                binding: 0,
-                                ty: ShaderUniformType::Sampler(sampler),
-                            },
-                            ShaderUniform {
-                                set: 2,
-                                binding: 1,
-                                ty: ShaderUniformType::Texture(texture_id),
-                            },
-                        ],
-                        Some(NewProgramExt {
-                            enable_blend: Some(()),
-                            blend_color_operation: Some(ShaderBlendOperation::Add),
-                            blend_color_src_factor: Some(ShaderBlendFactor::SrcAlpha),
-                            blend_color_dst_factor: Some(ShaderBlendFactor::OneMinusSrcAlpha),
-                            blend_alpha_operation: Some(ShaderBlendOperation::Add),
-                            blend_alpha_src_factor: Some(ShaderBlendFactor::SrcAlpha),
-                            blend_alpha_dst_factor: Some(ShaderBlendFactor::OneMinusSrcAlpha),
-                            ..Default::default()
-                        }),
-                    )
-                    .unwrap()
+                texture_id
             })
             .collect::<Vec<_>>();
 
+        let mut new_sprite_program = |texture_id: TextureId, blend: SpriteBlend| {
+            context
+                .new_program(
+                    &shader_set,
+                    &[
+                        ShaderUniform {
+                            set: 0,
+                            binding: 0,
+                            ty: ShaderUniformType::UniformBuffer(scene_ubo),
+                        },
+                        ShaderUniform {
+                            set: 1,
+                            binding: 0,
+                            ty: ShaderUniformType::DynamicUniformBuffer(sprite_ubo),
+                        },
+                        ShaderUniform {
+                            set: 2,
+                            binding: 0,
+                            ty: ShaderUniformType::Sampler(sampler),
+                        },
+                        ShaderUniform {
+                            set: 2,
+                            binding: 1,
+                            ty: ShaderUniformType::Texture(texture_id),
+                        },
+                    ],
+                    Some(blend_ext(blend)),
+                )
+                .unwrap()
+        };
+
+        // Programs belong to a single pass step and blend mode, so every texture needs one for
+        // each combination.
+        let mut new_sprite_programs = |texture_id: TextureId| {
+            [
+                SpriteBlend::Alpha,
+                SpriteBlend::Additive,
+                SpriteBlend::Multiply,
+            ]
+            .map(|blend| new_sprite_program(texture_id, blend))
+        };
+        let lit_programs = texture_ids[..textures.len()]
+            .iter()
+            .map(|&texture_id| new_sprite_programs(texture_id))
+            .collect::<Vec<_>>();
+        let unlit_programs = texture_ids[..textures.len()]
+            .iter()
+            .map(|&texture_id| new_sprite_programs(texture_id))
+            .collect::<Vec<_>>();
+        let light_program = new_sprite_program(texture_ids[textures.len()], SpriteBlend::Additive);
+
+        let composite_program = context
+            .new_program(
+                &composite_shader_set,
+                &[
+                    ShaderUniform {
+                        set: 0,
+                        binding: 0,
+                        ty: ShaderUniformType::Sampler(sampler),
+                    },
+                    ShaderUniform {
+                        set: 0,
+                        binding: 1,
+                        ty: ShaderUniformType::InputAttachment(scene_attachment),
+                    },
+                    ShaderUniform {
+                        set: 0,
+                        binding: 2,
+                        ty: ShaderUniformType::InputAttachment(light_attachment),
+                    },
+                ],
+                None,
+            )
+            .unwrap();
+
         let vbo = context
             .new_vertex_buffer(quad_vertices(), BufferStorageType::Static, None)
             .unwrap();
@@ -161,24 +239,52 @@ impl Renderer {
             .new_index_buffer(quad_indices(), BufferStorageType::Static, None)
             .unwrap();
 
-        let mut pass = Pass::new(
-            width,
-            height,
-            Some(NewPassExt {
-                depends_on_surface_size: Some(()),
-                surface_attachment_load_op: Some(PassInputLoadOpColorType::Clear),
-            }),
-        );
+        let scene_step_dependency = {
+            let pass_step = pass.add_step();
+            pass_step
+                .add_vertex_buffer(vbo)
+                .set_index_buffer(ibo)
+                .add_write_color(scene_attachment);
+
+            for program in lit_programs.iter().flatten().copied() {
+                pass_step.add_program(program);
+            }
+            pass_step.get_step_dependency()
+        };
+
+        let light_step_dependency = {
+            let pass_step = pass.add_step();
+            pass_step
+                .add_vertex_buffer(vbo)
+                .set_index_buffer(ibo)
+                .add_write_color(light_attachment)
+                .add_program(light_program);
+            pass_step.get_step_dependency()
+        };
+
+        let composite_step_dependency = {
+            let pass_step = pass.add_step();
+            pass_step
+                .add_vertex_buffer(vbo)
+                .set_index_buffer(ibo)
+                .add_write_color(output_attachment)
+                .read_local_attachment(scene_attachment)
+                .read_local_attachment(light_attachment)
+                .set_wait_for_color_from_step(scene_step_dependency, ShaderStage::Fragment)
+                .set_wait_for_color_from_step(light_step_dependency, ShaderStage::Fragment)
+                .add_program(composite_program);
+            pass_step.get_step_dependency()
+        };
 
-        let output_attachment = pass.get_surface_local_attachment();
         {
             let pass_step = pass.add_step();
             pass_step
                 .add_vertex_buffer(vbo)
                 .set_index_buffer(ibo)
-                .add_write_color(output_attachment);
+                .add_write_color(output_attachment)
+                .set_wait_for_color_from_step(composite_step_dependency, ShaderStage::Fragment);
 
-            for program in programs.iter().copied() {
+            for program in unlit_programs.iter().flatten().copied() {
                 pass_step.add_program(program);
             }
         }
@@ -197,13 +303,25 @@ impl Renderer {
             sprite_ubo,
             sprite_ubo_guard,
 
-            programs,
+            lit_programs,
+            unlit_programs,
+            light_program,
+            composite_program,
+
             output_attachment,
+            scene_attachment,
+            light_attachment,
             compiled_pass,
         }
     }
 
-    pub fn draw(&mut self, camera: &RendererCamera, sprites: Vec<Sprite>) {
+    pub fn draw(
+        &mut self,
+        camera: &RendererCamera,
+        ambient: glm::Vec3,
+        sprites: Vec<Sprite>,
+        lights: Vec<Light>,
+    ) {
         let mut submit = Submit::new();
 
         let view = glm::translate(
@@ -214,22 +332,49 @@ impl Renderer {
         let scene = Scene { view };
         submit.transfer_into_uniform_buffer(self.scene_ubo_guard, &scene);
 
+        let sprites = sprites
+            .into_iter()
+            .filter(|s| s.color.w > 0.0)
+            .take(MAX_SPRITES)
+            .collect::<Vec<_>>();
+        let lights = lights.into_iter().take(MAX_LIGHTS).collect::<Vec<_>>();
+
         let mut sprite_uniforms = sprites
             .iter()
             .map(|s| {
                 let model = glm::identity();
                 let model = glm::translate(&model, &glm::vec3(s.position.x, s.position.y, 0.0));
                 let model = glm::rotate(&model, s.rotation, &glm::vec3(0.0, 0.0, 1.0));
-                let model = glm::scale(&model, &glm::vec3(s.scale.x, s.scale.y, 1.0));
+                let flip = glm::vec2(
+                    if s.flip_x { -1.0 } else { 1.0 },
+                    if s.flip_y { -1.0 } else { 1.0 },
+                );
+                let model = glm::scale(
+                    &model,
+                    &glm::vec3(s.scale.x * flip.x, s.scale.y * flip.y, 1.0),
+                );
 
                 SpriteUniform {
-                    visible: s.visible,
                     color: s.color,
+                    uv: s.uv,
                     model,
                 }
             })
             .collect::<Vec<_>>();
         sprite_uniforms.resize(MAX_SPRITES, SpriteUniform::default());
+        sprite_uniforms.extend(lights.iter().map(|l| {
+            let model = glm::identity();
+            let model = glm::translate(&model, &glm::vec3(l.position.x, l.position.y, 0.0));
+            let model = glm::scale(&model, &glm::vec3(l.radius * 2.0, l.radius * 2.0, 1.0));
+
+            let color = l.color * l.intensity;
+            SpriteUniform {
+                color: glm::vec4(color.x, color.y, color.z, 1.0),
+                uv: glm::vec4(0.0, 0.0, 1.0, 1.0),
+                model,
+            }
+        }));
+        sprite_uniforms.resize(MAX_SPRITES + MAX_LIGHTS, SpriteUniform::default());
         sprite_uniforms
             .iter()
             .enumerate()
@@ -240,16 +385,42 @@ impl Renderer {
         let mut pass_submit = PassSubmitData::new(self.compiled_pass);
 
         {
-            let mut step_submit = StepSubmitData::new();
-
-            for (dyn_idx, sprite) in (0..sprite_uniforms.len()).zip(sprites.iter()) {
+            let mut scene_step_submit = StepSubmitData::new();
+            let mut light_step_submit = StepSubmitData::new();
+            let mut composite_step_submit = StepSubmitData::new();
+            let mut unlit_step_submit = StepSubmitData::new();
+
+            for (dyn_idx, sprite) in sprites.iter().enumerate() {
+                let (step_submit, programs) = if sprite.unlit {
+                    (&mut unlit_step_submit, &self.unlit_programs)
+                } else {
+                    (&mut scene_step_submit, &self.lit_programs)
+                };
+                let program = programs[sprite.texture_index][sprite.blend as usize];
                 step_submit
-                    .draw_indexed(self.programs[sprite.texture_index], 0, quad_indices().len())
+                    .draw_indexed(program, 0, quad_indices().len())
+                    .set_dynamic_uniform_buffer_index(self.sprite_ubo, dyn_idx);
+            }
+
+            for dyn_idx in MAX_SPRITES..MAX_SPRITES + lights.len() {
+                light_step_submit
+                    .draw_indexed(self.light_program, 0, quad_indices().len())
                     .set_dynamic_uniform_buffer_index(self.sprite_ubo, dyn_idx);
             }
 
+            composite_step_submit.draw_indexed(self.composite_program, 0, quad_indices().len());
+
             pass_submit.set_attachment_clear_color(
                 self.output_attachment,
+                ClearColor {
+                    r: 0.0,
+                    g: 0.0,
+                    b: 0.0,
+                    a: 1.0,
+                },
+            );
+            pass_submit.set_attachment_clear_color(
+                self.scene_attachment,
                 ClearColor {
                     r: 0.027,
                     g: 0.01,
@@ -257,7 +428,19 @@ impl Renderer {
                     a: 1.0,
                 },
             );
-            pass_submit.step(step_submit);
+            pass_submit.set_attachment_clear_color(
+                self.light_attachment,
+                ClearColor {
+                    r: ambient.x,
+                    g: ambient.y,
+                    b: ambient.z,
+                    a: 1.0,
+                },
+            );
+            pass_submit.step(scene_step_submit);
+            pass_submit.step(light_step_submit);
+            pass_submit.step(composite_step_submit);
+            pass_submit.step(unlit_step_submit);
         }
 
         submit.pass(pass_submit);
@@ -265,6 +448,51 @@ impl Renderer {
     }
 }
 
+fn translate_shader(
+    context: &mut Context,
+    stage: naga_translation::NagaTranslationStage,
+    code: &[u8],
+) -> Vec<u8> {
+    context
+        .naga_translate_shader_code(
+            stage,
+            naga_translation::NagaTranslationInput::Spirv,
+            code,
+            naga_translation::NagaTranslationExtensionTranslateShaderCodeExt::default(),
+        )
+        .unwrap()
+}
+
+fn blend_ext(blend: SpriteBlend) -> NewProgramExt {
+    let (src, dst) = match blend {
+        SpriteBlend::Alpha => (
+            ShaderBlendFactor::SrcAlpha,
+            ShaderBlendFactor::OneMinusSrcAlpha,
+        ),
+        SpriteBlend::Additive => (ShaderBlendFactor::SrcAlpha, ShaderBlendFactor::One),
+        SpriteBlend::Multiply => (ShaderBlendFactor::DstColor, ShaderBlendFactor::Zero),
+    };
+    NewProgramExt {
+        enable_blend: Some(()),
+        blend_color_operation: Some(ShaderBlendOperation::Add),
+        blend_color_src_factor: Some(src),
+        blend_color_dst_factor: Some(dst),
+        blend_alpha_operation: Some(ShaderBlendOperation::Add),
+        blend_alpha_src_factor: Some(ShaderBlendFactor::SrcAlpha),
+        blend_alpha_dst_factor: Some(ShaderBlendFactor::OneMinusSrcAlpha),
+        ..Default::default()
+    }
+}
+
+// Smooth radial falloff used for every point light.
+fn light_texture() -> TextureData {
+    TextureData::from_fn(LIGHT_TEXTURE_SIZE, LIGHT_TEXTURE_SIZE, |x, y| {
+        let falloff = (1.0 - (x * x + y * y).sqrt()).clamp(0.0, 1.0);
+        let falloff = falloff * falloff * (3.0 - 2.0 * falloff);
+        [255, 255, 255, (falloff * 255.0) as u8]
+    })
+}
+
 pub fn graphics_init(galaxy: &Galaxy) {
     let raw_window = galaxy
         .get_resource::<window::RawWindowRes, _>(window::RawWindowRes::single_resource())
@@ -304,5 +532,18 @@ pub fn graphics_update(galaxy: &Galaxy) {
         .copied()
         .collect::<Vec<_>>();
 
-    renderer.0.draw(&camera, sprites);
+    let lights = galaxy
+        .get_events::<RendererDrawLight>()
+        .iter()
+        .map(|RendererDrawLight(light)| light)
+        .copied()
+        .collect::<Vec<_>>();
+
+    let ambient = galaxy
+        .get_events::<RendererAmbientLight>()
+        .last()
+        .map(|RendererAmbientLight(ambient)| *ambient)
+        .unwrap_or(glm::vec3(1.0, 1.0, 1.0));
+
+    renderer.0.draw(&camera, ambient, sprites, lights);
 }
@@ -0,0 +1,15 @@
+#version 460
+
+layout(location = 0) in vec2 texture_coord;
+
+layout(location = 0) out vec4 o_color;
+
+layout(set = 0, binding = 0) uniform sampler u_sampler;
+layout(set = 0, binding = 1) uniform texture2D u_scene;
+layout(set = 0, binding = 2) uniform texture2D u_light;
+
+void main() {
+    vec3 scene = texture(sampler2D(u_scene, u_sampler), texture_coord).rgb;
+    vec3 light = texture(sampler2D(u_light, u_sampler), texture_coord).rgb;
+    o_color = vec4(scene * light, 1.0);
+}
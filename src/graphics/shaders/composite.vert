@@ -0,0 +1,11 @@
+#version 460
+
+layout(location = 0) in vec2 a_position;
+layout(location = 1) in vec2 a_texture_coord;
+
+layout(location = 0) out vec2 texture_coord;
+
+void main() {
+    gl_Position = vec4(a_position * 2.0, 0.0, 1.0);
+    texture_coord = a_texture_coord;
+}
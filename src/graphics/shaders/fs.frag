@@ -9,11 +9,11 @@ layout(set = 2, binding = 1) uniform texture2D u_textures;
 
 layout(set = 1, binding = 0) uniform Sprite {
     mat4 model;
-    vec3 color;
-    float visible;
+    vec4 color;
+    vec4 uv;
 };
 
 void main() {
-    o_color = texture(sampler2D(u_textures, u_sampler), texture_coord) * vec4(color, 1.0);
+    o_color = texture(sampler2D(u_textures, u_sampler), texture_coord) * color;
 }
 
@@ -9,14 +9,14 @@ layout(set = 0, binding = 0) uniform Scene {
 
 layout(set = 1, binding = 0) uniform Sprite {
     mat4 model;
-    vec3 color;
-    float visible;
+    vec4 color;
+    vec4 uv;
 };
 
 layout(location = 0) out vec2 texture_coord;
 
 void main() {
-    gl_Position = view * model * vec4(a_position, 0.0, visible);
-    texture_coord = a_texture_coord;
+    gl_Position = view * model * vec4(a_position, 0.0, 1.0);
+    texture_coord = uv.xy + a_texture_coord * uv.zw;
 }
 
@@ -13,6 +13,24 @@ impl TextureData {
         }
     }
 
+    /// Generate a texture from `f(x, y)`, where both coordinates range from -1.0 to 1.0.
+    pub fn from_fn(width: usize, height: usize, f: impl Fn(f32, f32) -> [u8; 4]) -> Self {
+        let mut data = Vec::with_capacity(width * height * 4);
+        for row in 0..height {
+            for col in 0..width {
+                let x = (col as f32 + 0.5) / width as f32 * 2.0 - 1.0;
+                let y = 1.0 - (row as f32 + 0.5) / height as f32 * 2.0;
+                data.extend_from_slice(&f(x, y));
+            }
+        }
+
+        Self {
+            data,
+            width,
+            height,
+        }
+    }
+
     pub fn load(bytes: &[u8]) -> Self {
         let image = image::load_from_memory(bytes).unwrap();
         let width = image.width() as usize;
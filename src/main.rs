@@ -5,10 +5,14 @@ use rand::prelude::*;
 mod audio;
 mod game_scene;
 mod graphics;
+mod replay;
+mod storage;
 mod window;
 
 pub use audio::*;
 pub use graphics::*;
+pub use replay::*;
+pub use storage::*;
 pub use window::*;
 
 fn main() {
@@ -17,3 +21,10 @@ fn main() {
 
     game_scene::run();
 }
+
+/// `None` if `name` wasn't passed, otherwise the value following it, if any.
+pub fn cli_arg(name: &str) -> Option<Option<String>> {
+    let mut args = std::env::args().skip_while(|arg| arg != name);
+    args.next()?;
+    Some(args.next().filter(|arg| !arg.starts_with("--")))
+}
@@ -0,0 +1,508 @@
+//! Records every tick's window events, window size and frame time so a session can be played
+//! back exactly. The last session is kept in storage, written every few seconds and when the
+//! game panics. Run with `--replay [path]` to play one back, where Space pauses, Tab fast
+//! forwards while held and `.` steps a single tick while paused. The game's setup, like its
+//! settings and tuning, is recorded along with any changes to it. Saves aren't, so sessions
+//! that continued a run only replay on a machine with the same save, and pass the same
+//! `--level` if the session was played on one.
+
+use super::*;
+use instant::Instant;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{AxisId, DeviceId, Ime, ModifiersState, MouseScrollDelta, Touch, TouchPhase},
+    window::Theme,
+};
+
+const REPLAY_FILE: &str = "replay.ron";
+const MAX_FRAME_DELTA: Duration = Duration::from_millis(250);
+const FAST_FORWARD_TICKS: usize = 8;
+/// How often the recording is written out, so a crash or a closed tab loses little of it.
+const FLUSH_TICKS: u64 = 300;
+/// Recording stops once the replay would get bigger than this. Browsers only give a few
+/// megabytes of local storage.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+const MAX_REPLAY_BYTES: usize = 2_000_000;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+const MAX_REPLAY_BYTES: usize = 200_000_000;
+
+/// Where new runs get their seeds from. Pinned seeds are used for every run as is.
+#[derive(SingleResource, Clone, Copy, Serialize, Deserialize)]
+pub struct SessionSeed {
+    pub seed: u64,
+    pub pinned: bool,
+}
+
+/// Real time since the last tick, or the recorded time when playing back.
+#[derive(SingleResource, Default, Clone, Copy)]
+pub struct FrameDelta(pub Duration);
+
+/// The game's own setup as RON, which the replay keeps without knowing what's in it. The game
+/// hands it to `record` whenever it changes, and takes it back from `play` at the same tick
+/// while playing back.
+#[derive(SingleResource, Default)]
+pub struct ReplaySetup {
+    pub record: Option<String>,
+    pub play: Option<String>,
+}
+
+#[derive(SingleResource, Default, Clone, Copy)]
+pub struct ReplayStatus {
+    pub playing: bool,
+    pub paused: bool,
+    pub fast_forward: bool,
+    pub tick: u64,
+    pub ticks: u64,
+}
+
+/// A window event without the parts that can't be saved, like device ids.
+#[derive(Clone, Serialize, Deserialize)]
+enum RecordedEvent {
+    Resized(PhysicalSize<u32>),
+    Moved(PhysicalPosition<i32>),
+    DroppedFile(PathBuf),
+    HoveredFile(PathBuf),
+    HoveredFileCancelled,
+    ReceivedCharacter(char),
+    Focused(bool),
+    KeyboardInput(KeyboardInput),
+    ModifiersChanged(ModifiersState),
+    Ime(Ime),
+    CursorMoved(PhysicalPosition<f64>),
+    CursorEntered,
+    CursorLeft,
+    MouseWheel(MouseScrollDelta, TouchPhase),
+    MouseInput(ElementState, MouseButton),
+    TouchpadMagnify(f64, TouchPhase),
+    SmartMagnify,
+    TouchpadRotate(f32, TouchPhase),
+    TouchpadPressure(f32, i64),
+    AxisMotion(AxisId, f64),
+    /// Phase, location and finger. The pressure isn't kept.
+    Touch(TouchPhase, PhysicalPosition<f64>, u64),
+    ThemeChanged(Theme),
+    Occluded(bool),
+}
+
+impl RecordedEvent {
+    /// `None` for events that only the window loop deals with.
+    fn record(event: &WindowEventData<'static>) -> Option<Self> {
+        use WindowEventData as E;
+        Some(match event.clone() {
+            E::Resized(size) => Self::Resized(size),
+            E::Moved(position) => Self::Moved(position),
+            E::DroppedFile(path) => Self::DroppedFile(path),
+            E::HoveredFile(path) => Self::HoveredFile(path),
+            E::HoveredFileCancelled => Self::HoveredFileCancelled,
+            E::ReceivedCharacter(c) => Self::ReceivedCharacter(c),
+            E::Focused(focused) => Self::Focused(focused),
+            E::KeyboardInput { input, .. } => Self::KeyboardInput(input),
+            E::ModifiersChanged(modifiers) => Self::ModifiersChanged(modifiers),
+            E::Ime(ime) => Self::Ime(ime),
+            E::CursorMoved { position, .. } => Self::CursorMoved(position),
+            E::CursorEntered { .. } => Self::CursorEntered,
+            E::CursorLeft { .. } => Self::CursorLeft,
+            E::MouseWheel { delta, phase, .. } => Self::MouseWheel(delta, phase),
+            E::MouseInput { state, button, .. } => Self::MouseInput(state, button),
+            E::TouchpadMagnify { delta, phase, .. } => Self::TouchpadMagnify(delta, phase),
+            E::SmartMagnify { .. } => Self::SmartMagnify,
+            E::TouchpadRotate { delta, phase, .. } => Self::TouchpadRotate(delta, phase),
+            E::TouchpadPressure {
+                pressure, stage, ..
+            } => Self::TouchpadPressure(pressure, stage),
+            E::AxisMotion { axis, value, .. } => Self::AxisMotion(axis, value),
+            E::Touch(touch) => Self::Touch(touch.phase, touch.location, touch.id),
+            E::ThemeChanged(theme) => Self::ThemeChanged(theme),
+            E::Occluded(occluded) => Self::Occluded(occluded),
+            E::CloseRequested | E::Destroyed | E::ScaleFactorChanged { .. } => return None,
+        })
+    }
+
+    #[allow(deprecated)]
+    fn play(self) -> WindowEventData<'static> {
+        use WindowEventData as E;
+        // Only ever compared against other ids, never handed back to winit.
+        let device_id = unsafe { DeviceId::dummy() };
+        let modifiers = ModifiersState::empty();
+        match self {
+            Self::Resized(size) => E::Resized(size),
+            Self::Moved(position) => E::Moved(position),
+            Self::DroppedFile(path) => E::DroppedFile(path),
+            Self::HoveredFile(path) => E::HoveredFile(path),
+            Self::HoveredFileCancelled => E::HoveredFileCancelled,
+            Self::ReceivedCharacter(c) => E::ReceivedCharacter(c),
+            Self::Focused(focused) => E::Focused(focused),
+            Self::KeyboardInput(input) => E::KeyboardInput {
+                device_id,
+                input,
+                is_synthetic: false,
+            },
+            Self::ModifiersChanged(modifiers) => E::ModifiersChanged(modifiers),
+            Self::Ime(ime) => E::Ime(ime),
+            Self::CursorMoved(position) => E::CursorMoved {
+                device_id,
+                position,
+                modifiers,
+            },
+            Self::CursorEntered => E::CursorEntered { device_id },
+            Self::CursorLeft => E::CursorLeft { device_id },
+            Self::MouseWheel(delta, phase) => E::MouseWheel {
+                device_id,
+                delta,
+                phase,
+                modifiers,
+            },
+            Self::MouseInput(state, button) => E::MouseInput {
+                device_id,
+                state,
+                button,
+                modifiers,
+            },
+            Self::TouchpadMagnify(delta, phase) => E::TouchpadMagnify {
+                device_id,
+                delta,
+                phase,
+            },
+            Self::SmartMagnify => E::SmartMagnify { device_id },
+            Self::TouchpadRotate(delta, phase) => E::TouchpadRotate {
+                device_id,
+                delta,
+                phase,
+            },
+            Self::TouchpadPressure(pressure, stage) => E::TouchpadPressure {
+                device_id,
+                pressure,
+                stage,
+            },
+            Self::AxisMotion(axis, value) => E::AxisMotion {
+                device_id,
+                axis,
+                value,
+            },
+            Self::Touch(phase, location, id) => E::Touch(Touch {
+                device_id,
+                phase,
+                location,
+                force: None,
+                id,
+            }),
+            Self::ThemeChanged(theme) => E::ThemeChanged(theme),
+            Self::Occluded(occluded) => E::Occluded(occluded),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct RecordedInput {
+    tick: u64,
+    event: RecordedEvent,
+}
+
+/// The window size from this tick on.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct RecordedSize {
+    tick: u64,
+    width: usize,
+    height: usize,
+}
+
+/// The game's setup from this tick on, see `ReplaySetup`.
+#[derive(Clone, Serialize, Deserialize)]
+struct RecordedSetup {
+    tick: u64,
+    setup: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Replay {
+    session: Option<SessionSeed>,
+    /// Frame time of every tick in microseconds.
+    deltas: Vec<u32>,
+    inputs: Vec<RecordedInput>,
+    sizes: Vec<RecordedSize>,
+    #[serde(default)]
+    setups: Vec<RecordedSetup>,
+}
+
+impl Replay {
+    /// Returns `false`, leaving what was written before alone, once it has grown too big.
+    fn write(&self) -> bool {
+        let data = ron::to_string(self).unwrap();
+        if data.len() > MAX_REPLAY_BYTES {
+            return false;
+        }
+        storage_write(REPLAY_FILE, &data);
+        true
+    }
+}
+
+pub struct ReplayDriver {
+    /// Shared with the panic hook, which writes out the recording.
+    replay: Arc<Mutex<Replay>>,
+    playback: bool,
+    /// Stopped because the recording got too big.
+    full: bool,
+    tick: u64,
+    next_input: usize,
+    next_size: usize,
+    next_setup: usize,
+    /// Recorded input that arrived since the last tick.
+    pending: Vec<RecordedEvent>,
+    last_tick: Instant,
+    paused: bool,
+    fast_forward: bool,
+    step: bool,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn replay_read(path: Option<String>) -> Option<String> {
+    match path {
+        Some(path) => std::fs::read_to_string(path).ok(),
+        None => storage_read(REPLAY_FILE),
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn replay_read(_path: Option<String>) -> Option<String> {
+    storage_read(REPLAY_FILE)
+}
+
+impl ReplayDriver {
+    /// Plays back the replay given by `--replay`, or records a new one.
+    pub fn from_args() -> Self {
+        let recorded = cli_arg("--replay")
+            .and_then(replay_read)
+            .and_then(|data| ron::from_str::<Replay>(&data).ok())
+            .filter(|replay| replay.session.is_some());
+        let playback = recorded.is_some();
+
+        let replay = Arc::new(Mutex::new(recorded.unwrap_or_else(|| {
+            let pinned = cli_arg("--seed")
+                .flatten()
+                .and_then(|seed| seed.parse().ok());
+            Replay {
+                session: Some(SessionSeed {
+                    seed: pinned.unwrap_or_else(|| rand::thread_rng().gen::<u32>() as u64),
+                    pinned: pinned.is_some(),
+                }),
+                ..Default::default()
+            }
+        })));
+
+        if !playback {
+            let hook = std::panic::take_hook();
+            let replay = replay.clone();
+            std::panic::set_hook(Box::new(move |info| {
+                // Held only while a tick is being recorded, never while the game runs.
+                if let Ok(replay) = replay.try_lock() {
+                    replay.write();
+                }
+                hook(info);
+            }));
+        }
+
+        Self {
+            playback,
+            replay,
+            full: false,
+            tick: 0,
+            next_input: 0,
+            next_size: 0,
+            next_setup: 0,
+            pending: vec![],
+            last_tick: Instant::now(),
+            paused: false,
+            fast_forward: false,
+            step: false,
+        }
+    }
+
+    fn replay(&self) -> MutexGuard<'_, Replay> {
+        self.replay.lock().unwrap()
+    }
+
+    pub fn session_seed(&self) -> SessionSeed {
+        self.replay().session.unwrap()
+    }
+
+    /// What the game starts out from, which is the recorded setup when playing back.
+    pub fn setup(&mut self) -> ReplaySetup {
+        let replay = self.replay.clone();
+        let replay = replay.lock().unwrap();
+        let mut play = None;
+        while let Some(recorded) = replay.setups.get(self.next_setup) {
+            if recorded.tick != 0 {
+                break;
+            }
+            play = Some(recorded.setup.clone());
+            self.next_setup += 1;
+        }
+        ReplaySetup { record: None, play }
+    }
+
+    pub fn status(&self) -> ReplayStatus {
+        ReplayStatus {
+            playing: self.playback,
+            paused: self.paused,
+            fast_forward: self.fast_forward,
+            tick: self.tick,
+            ticks: self.replay().deltas.len() as u64,
+        }
+    }
+
+    /// Returns the event to pass on to the game. Events are recorded, or during a replay
+    /// swallowed so only the recording drives the game, with the keyboard working the
+    /// playback controls.
+    pub fn filter_event(
+        &mut self,
+        event: WindowEventData<'static>,
+    ) -> Option<WindowEventData<'static>> {
+        if !self.playback {
+            if !self.full {
+                self.pending.extend(RecordedEvent::record(&event));
+            }
+            return Some(event);
+        }
+
+        if let WindowEventData::KeyboardInput { input, .. } = event {
+            let pressed = input.state == ElementState::Pressed;
+            match input.virtual_keycode {
+                Some(VirtualKeyCode::Space) if pressed => self.paused = !self.paused,
+                Some(VirtualKeyCode::Tab) => self.fast_forward = pressed,
+                Some(VirtualKeyCode::Period) if pressed => self.step = true,
+                _ => (),
+            }
+        }
+        None
+    }
+
+    /// How many ticks to run this frame.
+    pub fn ticks(&mut self, galaxy: &Galaxy) -> usize {
+        **galaxy
+            .get_mut_resource::<ReplayStatus, _>(ReplayStatus::single_resource())
+            .unwrap() = self.status();
+
+        if !self.playback {
+            1
+        } else if self.paused {
+            std::mem::take(&mut self.step) as usize
+        } else if self.fast_forward {
+            FAST_FORWARD_TICKS
+        } else {
+            1
+        }
+    }
+
+    /// Start a tick: sets `FrameDelta` and `WindowSize`, and feeds in the recorded events when
+    /// playing back. Returns `false` once a replay has run out, which holds it on the last frame.
+    pub fn tick(&mut self, galaxy: &Galaxy, window_size: WindowSize) -> bool {
+        let delta = if self.playback {
+            let replay = self.replay.clone();
+            let replay = replay.lock().unwrap();
+            let Some(&delta) = replay.deltas.get(self.tick as usize) else {
+                self.paused = true;
+                return false;
+            };
+            while let Some(recorded) = replay.inputs.get(self.next_input) {
+                if recorded.tick != self.tick {
+                    break;
+                }
+                galaxy.insert_event(WindowEvent(recorded.event.clone().play()));
+                self.next_input += 1;
+            }
+            while let Some(recorded) = replay.sizes.get(self.next_size) {
+                if recorded.tick != self.tick {
+                    break;
+                }
+                **galaxy
+                    .get_mut_resource::<WindowSize, _>(WindowSize::single_resource())
+                    .unwrap() = WindowSize {
+                    width: recorded.width,
+                    height: recorded.height,
+                };
+                self.next_size += 1;
+            }
+            while let Some(recorded) = replay.setups.get(self.next_setup) {
+                if recorded.tick != self.tick {
+                    break;
+                }
+                galaxy
+                    .get_mut_resource::<ReplaySetup, _>(ReplaySetup::single_resource())
+                    .unwrap()
+                    .play = Some(recorded.setup.clone());
+                self.next_setup += 1;
+            }
+            Duration::from_micros(delta as u64)
+        } else {
+            let now = Instant::now();
+            let delta = now.duration_since(self.last_tick).min(MAX_FRAME_DELTA);
+            self.last_tick = now;
+            **galaxy
+                .get_mut_resource::<WindowSize, _>(WindowSize::single_resource())
+                .unwrap() = window_size;
+            if !self.full {
+                let setup = galaxy
+                    .get_mut_resource::<ReplaySetup, _>(ReplaySetup::single_resource())
+                    .unwrap()
+                    .record
+                    .take();
+                self.record(delta, window_size, setup);
+            }
+            // Recorded at microsecond precision, so play it the same way now.
+            Duration::from_micros(delta.as_micros() as u64)
+        };
+
+        **galaxy
+            .get_mut_resource::<FrameDelta, _>(FrameDelta::single_resource())
+            .unwrap() = FrameDelta(delta);
+        self.tick += 1;
+        true
+    }
+
+    /// `setup` was handed over during the last tick, or before the first.
+    fn record(&mut self, delta: Duration, window_size: WindowSize, setup: Option<String>) {
+        let tick = self.tick;
+        let pending = std::mem::take(&mut self.pending);
+        let mut replay = self.replay();
+        if let Some(setup) = setup {
+            replay.setups.push(RecordedSetup {
+                tick: tick.saturating_sub(1),
+                setup,
+            });
+        }
+        replay.deltas.push(delta.as_micros() as u32);
+        replay.inputs.extend(
+            pending
+                .into_iter()
+                .map(|event| RecordedInput { tick, event }),
+        );
+        let resized = replay.sizes.last().is_none_or(|last| {
+            last.width != window_size.width || last.height != window_size.height
+        });
+        if resized {
+            replay.sizes.push(RecordedSize {
+                tick,
+                width: window_size.width,
+                height: window_size.height,
+            });
+        }
+
+        if (tick + 1).is_multiple_of(FLUSH_TICKS) && !replay.write() {
+            drop(replay);
+            self.full = true;
+        }
+    }
+
+    /// Keep the session for `--replay`. Playback leaves the recording alone.
+    pub fn finish(&self) {
+        if !self.playback && !self.full {
+            self.replay().write();
+        }
+    }
+}
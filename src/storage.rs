@@ -0,0 +1,55 @@
+//! Small named blobs of text kept between runs.
+//! Files in the user data directory on desktop, `localStorage` on the web.
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn storage_path(name: &str) -> Option<std::path::PathBuf> {
+    Some(dirs::data_dir()?.join("treatsforthewicked").join(name))
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn storage_read(name: &str) -> Option<String> {
+    std::fs::read_to_string(storage_path(name)?).ok()
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn storage_write(name: &str, data: &str) {
+    let Some(path) = storage_path(name) else {
+        return;
+    };
+    let result =
+        std::fs::create_dir_all(path.parent().unwrap()).and_then(|_| std::fs::write(&path, data));
+    if let Err(e) = result {
+        eprintln!("Failed to write {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn storage_remove(name: &str) {
+    if let Some(path) = storage_path(name) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub fn storage_read(name: &str) -> Option<String> {
+    local_storage()?.get_item(name).ok()?
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub fn storage_write(name: &str, data: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(name, data);
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub fn storage_remove(name: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.remove_item(name);
+    }
+}
@@ -2,9 +2,16 @@ use super::*;
 use raw_window_handle::{
     HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
 };
-use winit::{event::Event as EventData, event_loop::EventLoop, window::Window};
+use serde::{Deserialize, Serialize};
+use winit::{
+    event::Event as EventData,
+    event_loop::EventLoop,
+    window::{Fullscreen, Window},
+};
 
-pub use winit::event::{ElementState, VirtualKeyCode, WindowEvent as WindowEventData};
+pub use winit::event::{
+    ElementState, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent as WindowEventData,
+};
 
 #[derive(Event)]
 pub struct WindowEvent(pub WindowEventData<'static>);
@@ -15,6 +22,21 @@ pub struct WindowResize {
     pub height: usize,
 }
 
+/// Current size of the window in pixels.
+#[derive(SingleResource, Clone, Copy)]
+pub struct WindowSize {
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Set this resource to switch the window in and out of fullscreen.
+#[derive(SingleResource, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    Fullscreen,
+}
+
 #[derive(SingleResource)]
 pub struct RawWindowRes {
     pub display: RawDisplayHandle,
@@ -40,7 +62,13 @@ impl WindowState {
     }
 }
 
-pub fn window_run(mut galaxy: Galaxy, pre_updates: &[fn(&Galaxy)], systems: &[fn(&Galaxy)]) {
+/// `closes` run when the window is asked to close, before the galaxy exits.
+pub fn window_run(
+    mut galaxy: Galaxy,
+    pre_updates: &[fn(&Galaxy)],
+    systems: &[fn(&Galaxy)],
+    closes: &[fn(&Galaxy)],
+) {
     let state = WindowState::new();
 
     let window_size = get_window_size(&state.window);
@@ -55,13 +83,30 @@ pub fn window_run(mut galaxy: Galaxy, pre_updates: &[fn(&Galaxy)], systems: &[fn
         },
     );
 
+    galaxy.insert_resource(WindowMode::single_resource(), WindowMode::default());
+    galaxy.insert_resource(
+        WindowSize::single_resource(),
+        WindowSize {
+            width: window_size.0,
+            height: window_size.1,
+        },
+    );
+
+    let mut replay = ReplayDriver::from_args();
+    galaxy.insert_resource(SessionSeed::single_resource(), replay.session_seed());
+    galaxy.insert_resource(FrameDelta::single_resource(), FrameDelta::default());
+    galaxy.insert_resource(ReplayStatus::single_resource(), replay.status());
+    galaxy.insert_resource(ReplaySetup::single_resource(), replay.setup());
+
     for pre_update in pre_updates.iter() {
         pre_update(&galaxy);
     }
     galaxy.update();
 
     let systems = systems.to_vec();
+    let closes = closes.to_vec();
     let mut last_window_size = window_size;
+    let mut last_window_mode = WindowMode::default();
     state.event_loop.run(move |event, _, control_flow| {
         control_flow.set_poll();
 
@@ -70,11 +115,20 @@ pub fn window_run(mut galaxy: Galaxy, pre_updates: &[fn(&Galaxy)], systems: &[fn
                 event: WindowEventData::CloseRequested,
                 ..
             } => {
+                for close in closes.iter() {
+                    close(&galaxy);
+                }
+                replay.finish();
                 control_flow.set_exit();
                 galaxy.set_exit();
             }
             EventData::WindowEvent { event, .. } => {
-                galaxy.insert_event(WindowEvent(event.to_static().unwrap()));
+                if let Some(event) = event
+                    .to_static()
+                    .and_then(|event| replay.filter_event(event))
+                {
+                    galaxy.insert_event(WindowEvent(event));
+                }
             }
             EventData::MainEventsCleared => {
                 let window_size = get_window_size(&state.window);
@@ -86,13 +140,36 @@ pub fn window_run(mut galaxy: Galaxy, pre_updates: &[fn(&Galaxy)], systems: &[fn
                     // });
                 }
                 last_window_size = window_size;
-
-                for system in systems.iter() {
-                    system(&galaxy);
-                }
-
-                if galaxy.update().is_none() {
-                    control_flow.set_exit();
+                let window_size = WindowSize {
+                    width: window_size.0,
+                    height: window_size.1,
+                };
+
+                for _ in 0..replay.ticks(&galaxy) {
+                    if !replay.tick(&galaxy, window_size) {
+                        break;
+                    }
+
+                    for system in systems.iter() {
+                        system(&galaxy);
+                    }
+
+                    let window_mode = *galaxy
+                        .get_resource::<WindowMode, _>(WindowMode::single_resource())
+                        .unwrap();
+                    if window_mode != last_window_mode {
+                        state.window.set_fullscreen(match window_mode {
+                            WindowMode::Windowed => None,
+                            WindowMode::Fullscreen => Some(Fullscreen::Borderless(None)),
+                        });
+                    }
+                    last_window_mode = window_mode;
+
+                    if galaxy.update().is_none() {
+                        replay.finish();
+                        control_flow.set_exit();
+                        break;
+                    }
                 }
             }
             _ => (),